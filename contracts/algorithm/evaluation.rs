@@ -0,0 +1,280 @@
+//! Router evaluation models
+//!
+//! The credibility of a router increases when it behaves honestly and decreases when it is caught doing evil.
+//! How much it changes is decided by the evaluation model selected through `EvaluationParams`.
+
+/// The credibility curves that can be selected
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+pub enum EvaluationModel {
+    /// The original curve of the prototype.
+    /// Honest behaviors increase the credibility by `10 * (cred - min) / range` below the middle credibility,
+    /// and by `10 * (max - cred) / range` above it. Evil behaviors decrease it by `20 * (cred - min) / range`
+    #[default]
+    Piecewise,
+    /// Honest behaviors gain `gain_bps` of the distance to the max credibility,
+    /// and evil behaviors lose `decay_bps` of the distance to the min credibility
+    ExponentialDecay,
+    /// The same as `Piecewise`, but every previous offence adds `repeat_penalty_bps` to the penalty
+    AsymmetricPenalty,
+    /// The same as `Piecewise`, but the credibility is kept in `[floor, ceiling]`,
+    /// and cannot recover until `cooldown` blocks after the last offence
+    FloorCeiling,
+}
+
+/// The range of credibility
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CredibilityRange {
+    pub min: u32,
+    pub middle: u32,
+    pub max: u32,
+}
+
+impl CredibilityRange {
+    /// The width of the range, `0` if `min` is not below `max`
+    pub fn range(&self) -> u32 {
+        self.max.saturating_sub(self.min)
+    }
+
+    /// `cred` moved into `[min, max]`
+    pub fn clamp(&self, cred: u32) -> u32 {
+        cred.max(self.min).min(self.max)
+    }
+}
+
+/// The evaluation model in use and its parameters
+#[derive(Debug, Clone, Default, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+pub struct EvaluationParams {
+    pub model: EvaluationModel,
+    /// `ExponentialDecay`: the ratio of the distance to the max credibility gained by one honest behavior, in 1/10,000
+    pub gain_bps: u32,
+    /// `ExponentialDecay`: the ratio of the distance to the min credibility lost by one evil behavior, in 1/10,000
+    pub decay_bps: u32,
+    /// `AsymmetricPenalty`: the extra penalty for every previous offence, in 1/10,000
+    pub repeat_penalty_bps: u32,
+    /// `FloorCeiling`: the lowest credibility
+    pub floor: u32,
+    /// `FloorCeiling`: the highest credibility
+    pub ceiling: u32,
+    /// `FloorCeiling`: the number of blocks after the last offence before the credibility can increase
+    pub cooldown: u32,
+}
+
+impl EvaluationParams {
+    /// The original curve with default parameters for the other models
+    pub fn piecewise(range: &CredibilityRange) -> Self {
+        EvaluationParams {
+            model: EvaluationModel::Piecewise,
+            gain_bps: 1000,
+            decay_bps: 2000,
+            repeat_penalty_bps: 5000,
+            floor: range.min,
+            ceiling: range.max,
+            cooldown: 100,
+        }
+    }
+
+    pub fn is_valid(&self, range: &CredibilityRange) -> bool {
+        (self.gain_bps <= 10000)
+            && (self.decay_bps <= 10000)
+            && (range.min <= self.floor)
+            && (self.floor <= self.ceiling)
+            && (self.ceiling <= range.max)
+    }
+
+    /// The credibility after one honest behavior
+    ///
+    /// `blocks_since_offence` is the number of blocks since the last offence of the router, `None` if it never offended.
+    /// A credibility out of the range is taken as the nearest bound, and the result is kept in the range
+    pub fn honest(&self, range: &CredibilityRange, cred: u32, blocks_since_offence: Option<u32>) -> u32 {
+        let cred = range.clamp(cred);
+
+        let cred_after = match self.model {
+            EvaluationModel::Piecewise | EvaluationModel::AsymmetricPenalty => piecewise_honest(range, cred),
+            EvaluationModel::ExponentialDecay => {
+                cred.saturating_add(ratio_of(range.max.saturating_sub(cred), self.gain_bps as u64, 10000))
+            }
+            EvaluationModel::FloorCeiling => {
+                let in_cooldown = match blocks_since_offence {
+                    Some(blocks) => blocks < self.cooldown,
+                    None => false,
+                };
+
+                if in_cooldown {
+                    cred.max(self.floor).min(self.ceiling)
+                } else {
+                    piecewise_honest(range, cred).max(self.floor).min(self.ceiling)
+                }
+            }
+        };
+
+        range.clamp(cred_after)
+    }
+
+    /// The credibility after one evil behavior
+    ///
+    /// `offences` is the number of the previous offences of the router.
+    /// A credibility out of the range is taken as the nearest bound, and the result is kept in the range
+    pub fn evil(&self, range: &CredibilityRange, cred: u32, offences: u32) -> u32 {
+        let cred = range.clamp(cred);
+
+        let cred_after = match self.model {
+            EvaluationModel::Piecewise => cred.saturating_sub(piecewise_penalty(range, cred)),
+            EvaluationModel::ExponentialDecay => {
+                cred.saturating_sub(ratio_of(cred.saturating_sub(range.min), self.decay_bps as u64, 10000))
+            }
+            EvaluationModel::AsymmetricPenalty => {
+                let factor = (self.repeat_penalty_bps as u64).saturating_mul(offences as u64).saturating_add(10000);
+                let penalty = ratio_of(piecewise_penalty(range, cred), factor, 10000);
+
+                cred.saturating_sub(penalty.min(cred.saturating_sub(range.min)))
+            }
+            EvaluationModel::FloorCeiling => {
+                cred.saturating_sub(piecewise_penalty(range, cred)).max(self.floor).min(self.ceiling)
+            }
+        };
+
+        range.clamp(cred_after)
+    }
+}
+
+/// `value * numerator / denominator`, which is `0` if `denominator` is `0`, and `u32::MAX` if it does not fit
+fn ratio_of(value: u32, numerator: u64, denominator: u64) -> u32 {
+    (value as u128 * numerator as u128)
+        .checked_div(denominator as u128)
+        .map_or(0, |ratio| u32::try_from(ratio).unwrap_or(u32::MAX))
+}
+
+fn piecewise_honest(range: &CredibilityRange, cred: u32) -> u32 {
+    let distance = if cred < range.middle {
+        cred.saturating_sub(range.min)
+    } else {
+        range.max.saturating_sub(cred)
+    };
+
+    cred.saturating_add(ratio_of(distance, 10, range.range() as u64))
+}
+
+fn piecewise_penalty(range: &CredibilityRange, cred: u32) -> u32 {
+    ratio_of(cred.saturating_sub(range.min), 20, range.range() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RANGE: CredibilityRange = CredibilityRange {
+        min: 0,
+        middle: 50,
+        max: 100,
+    };
+
+    #[test]
+    fn piecewise_matches_the_original_curve() {
+        let params = EvaluationParams::piecewise(&RANGE);
+
+        assert_eq!(params.honest(&RANGE, 10, None), 11);
+        assert_eq!(params.honest(&RANGE, 60, None), 64);
+        assert_eq!(params.evil(&RANGE, 60, 0), 48);
+    }
+
+    #[test]
+    fn asymmetric_penalty_grows_with_offences() {
+        let mut params = EvaluationParams::piecewise(&RANGE);
+        params.model = EvaluationModel::AsymmetricPenalty;
+
+        assert_eq!(params.evil(&RANGE, 60, 0), 48);
+        assert_eq!(params.evil(&RANGE, 60, 2), 36);
+        assert_eq!(params.evil(&RANGE, 60, 100), 0);
+    }
+
+    #[test]
+    fn floor_ceiling_waits_for_cooldown() {
+        let mut params = EvaluationParams::piecewise(&RANGE);
+        params.model = EvaluationModel::FloorCeiling;
+        params.floor = 20;
+        params.ceiling = 80;
+
+        assert_eq!(params.evil(&RANGE, 21, 0), 20);
+        assert_eq!(params.honest(&RANGE, 40, Some(10)), 40);
+        assert_eq!(params.honest(&RANGE, 40, Some(100)), 44);
+        assert_eq!(params.honest(&RANGE, 80, None), 80);
+    }
+
+    #[test]
+    fn exponential_decay_approaches_the_bounds() {
+        let mut params = EvaluationParams::piecewise(&RANGE);
+        params.model = EvaluationModel::ExponentialDecay;
+
+        assert_eq!(params.honest(&RANGE, 50, None), 55);
+        assert_eq!(params.evil(&RANGE, 50, 0), 40);
+    }
+
+    #[test]
+    fn credibility_below_the_min_is_taken_as_the_min() {
+        // the min is raised above the credibility of the router
+        let range = CredibilityRange {
+            min: 60,
+            middle: 70,
+            max: 100,
+        };
+        let mut params = EvaluationParams::piecewise(&RANGE);
+
+        // the piecewise curve cannot leave the min credibility
+        assert_eq!(params.honest(&range, 50, None), 60);
+        assert_eq!(params.evil(&range, 50, 0), 60);
+
+        params.model = EvaluationModel::AsymmetricPenalty;
+        assert_eq!(params.evil(&range, 50, u32::MAX), 60);
+
+        params.model = EvaluationModel::ExponentialDecay;
+        assert_eq!(params.honest(&range, 50, None), 64);
+        assert_eq!(params.evil(&range, 50, 0), 60);
+    }
+
+    #[test]
+    fn credibility_above_the_max_is_taken_as_the_max() {
+        let params = EvaluationParams::piecewise(&RANGE);
+
+        assert_eq!(params.honest(&RANGE, 120, None), 100);
+        assert_eq!(params.evil(&RANGE, 120, 0), 80);
+    }
+
+    #[test]
+    fn empty_range_keeps_the_credibility() {
+        let range = CredibilityRange {
+            min: 50,
+            middle: 50,
+            max: 50,
+        };
+        let inverted = CredibilityRange {
+            min: 100,
+            middle: 50,
+            max: 0,
+        };
+        let params = EvaluationParams::piecewise(&RANGE);
+
+        assert_eq!(params.honest(&range, 50, None), 50);
+        assert_eq!(params.evil(&range, 50, 0), 50);
+        assert_eq!(params.honest(&inverted, 50, None), 0);
+        assert_eq!(params.evil(&inverted, 50, 0), 0);
+    }
+
+    #[test]
+    fn penalty_of_a_narrow_range_stops_at_the_min() {
+        // the penalty `20 * 5 / 10` is larger than the distance to the min
+        let range = CredibilityRange {
+            min: 0,
+            middle: 5,
+            max: 10,
+        };
+        let mut params = EvaluationParams::piecewise(&range);
+
+        assert_eq!(params.evil(&range, 5, 0), 0);
+
+        params.model = EvaluationModel::FloorCeiling;
+        params.floor = 2;
+        assert_eq!(params.evil(&range, 5, 0), 2);
+    }
+}
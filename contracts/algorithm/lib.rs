@@ -1,34 +1,59 @@
-#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
 
 //! Note:
 //! The `algorithm_prototype` is to help users understand the mechanisms of the protocol stack more intuitively
 
-use ink_lang as ink;
-
 use payload::message_define::{IReceivedMessage};
+
+mod storage_index;
+// independent of the contract environment, and shared with `tools/replay`
+pub mod evaluation;
+pub mod selection;
+pub mod weight_tree;
+pub mod randomness;
  
-#[ink::contract]
+#[ink::contract(env = crate::randomness::RandomnessEnvironment)]
 mod algorithm {
 
-    use ink_storage::{
-        traits::{
-            SpreadLayout,
-            StorageLayout,
-            PackedLayout,
-            SpreadAllocate,
-            PackedAllocate,
-        },
+    use ink::storage::{
+        traits::StorageLayout,
+        Mapping,
     };
 
-    /// Simulation
-    #[derive(SpreadLayout, PackedLayout, SpreadAllocate, Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    use crate::storage_index::KeyIndex;
+    use crate::evaluation::{CredibilityRange, EvaluationModel, EvaluationParams};
+    use crate::selection;
+    use crate::weight_tree::WeightTree;
+    use crate::randomness::{self, RandomnessSource, SeededRandom, VrfKey, ROUND_SELECTION, ROUND_INSPECTION, DOMAIN_ROUTERS, DOMAIN_INSPECTION_DRAW, DOMAIN_INSPECTORS};
+
+    /// The status of a registered router
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo, StorageLayout))]
+    pub enum RouterStatus {
+        /// The router can be selected and submit message copies
+        Active,
+        /// The router has been caught by a fraud proof
+        Jailed,
+        /// The router is leaving the network
+        Exiting,
+    }
+
+    /// The record of a registered router
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
     #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo, StorageLayout))]
-    pub struct SimNode(u16, u32);
+    pub struct RouterInfo {
+        pub credibility: u32,
+        pub registered_at: BlockNumber,
+        pub status: RouterStatus,
+        pub metadata: ink::prelude::string::String,
+        /// the number of the evil behaviors been caught
+        pub offences: u32,
+        pub last_offence: Option<BlockNumber>,
+    }
 
-    impl PackedAllocate for SimNode {
-        fn allocate_packed(&mut self, at: &ink_primitives::Key) {
-            PackedAllocate::allocate_packed(&mut self.0, at);
-            PackedAllocate::allocate_packed(&mut self.1, at);
+    impl RouterInfo {
+        pub fn is_active(&self) -> bool {
+            self.status == RouterStatus::Active
         }
     }
 
@@ -36,61 +61,138 @@ mod algorithm {
     #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
     #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
     pub struct SelectionInterval {
-        pub id: u16,
+        pub id: AccountId,
         pub cre: u32,
-        pub low: u32,
-        pub high: u32,
+        /// the selection weight mixed from the credibility and the staking weight
+        pub weight: u128,
+        pub low: u128,
+        pub high: u128,
         pub selected: u16,
     }
 
     impl SelectionInterval {
-        pub fn contains(&self, value: u32) -> bool {
-            if value >= self.low && value < self.high {
-                true
-            } else {
-                false
-            }
+        pub fn contains(&self, value: u128) -> bool {
+            value >= self.low && value < self.high
         }
     }
 
     /// message simulation
-    #[derive(SpreadLayout, PackedLayout, Debug, scale::Encode, scale::Decode)]
+    #[derive(Debug, scale::Encode, scale::Decode)]
     #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo, StorageLayout))]
     pub struct MessageInfo {
         msg_hash: [u8;32],
         // the struct is `IReceivedMessage`
-        msg_detail: ink_prelude::vec::Vec<u8>,
-        submitters: ink_prelude::vec::Vec<u16>,
+        msg_detail: ink::prelude::vec::Vec<u8>,
+        submitters: ink::prelude::vec::Vec<AccountId>,
     }
 
     impl MessageInfo {
         pub fn get_submitter_count(&self) -> u16 {
-            self.submitters.len() as u16
+            u16::try_from(self.submitters.len()).unwrap_or(u16::MAX)
+        }
+    }
+
+    /// SQoS settings registered by a destination contract for the messages sent to it
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo, StorageLayout))]
+    pub struct SQoSProfile {
+        /// the number of the message copies needed for one message to be verified
+        pub copy_count: u16,
+        /// the credibility threshold, a factor of 10,000 was multiplied
+        pub vf_threshold: u128,
+        /// the number of blocks to wait for enough copies since the message was announced
+        pub timeout: BlockNumber,
+        /// whether a message delivered by only one copy can be accepted
+        pub allow_single_copy: bool,
+        /// the min number of copies with which a message is still verified after its timeout, a message with fewer copies fails
+        pub min_partial_copies: u16,
+    }
+
+    /// The reason code given to `Tokenomics::slash` for a router caught by a fraud proof
+    pub const SLASH_REASON_FRAUD_PROOF: u32 = 1;
+
+    /// Mirrors `SlashAmount::Fraction` of `Tokenomics`, such that it is encoded the same as the argument of `Tokenomics::slash`
+    #[derive(scale::Encode)]
+    enum TokenomicsSlashAmount {
+        Fraction(u32),
+    }
+
+    /// How the selection weight of a router is mixed from its credibility and its staking weight in `Tokenomics`
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo, StorageLayout))]
+    pub struct WeightPolicy {
+        /// the factor of the credibility, a factor of 10,000 was multiplied
+        pub cred_ratio: u32,
+        /// the factor of the staking weight, a factor of 10,000 was multiplied
+        pub stake_ratio: u32,
+        /// whether the routers without staking weight are excluded from the selection
+        pub require_stake: bool,
+    }
+
+    impl WeightPolicy {
+        pub fn is_valid(&self) -> bool {
+            (self.cred_ratio <= 10000)
+                && (self.stake_ratio <= 10000)
+                && ((self.cred_ratio > 0) || (self.stake_ratio > 0))
+        }
+    }
+
+    impl SQoSProfile {
+        pub fn is_valid(&self) -> bool {
+            (self.copy_count > 0)
+                && (self.timeout > 0)
+                && (self.vf_threshold <= 10000)
+                && ((self.copy_count > 1) || self.allow_single_copy)
+                && (self.min_partial_copies <= self.copy_count)
         }
     }
 
-    #[derive(SpreadLayout, PackedLayout, Debug, scale::Encode, scale::Decode)]
+    #[derive(Debug, scale::Encode, scale::Decode)]
     #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo, StorageLayout))]
     pub struct RecvedMessage {
         msg_id: u128,
-        msg_vec: ink_prelude::vec::Vec<MessageInfo>,
+        // the destination contract, whose SQoS settings apply to the message
+        destination: AccountId,
+        msg_vec: ink::prelude::vec::Vec<MessageInfo>,
+        // the block number when the message was announced
+        first_seen: BlockNumber,
         processed: bool,
+        // not enough copies were delivered before the timeout
+        failed: bool,
+        // held for an inspection round by Verifiable Passive Inspection
+        held: bool,
+        // the number of copies submitted by inspectors during the inspection round
+        inspection_count: u16,
     }
 
     impl RecvedMessage {
+        /// The record left after a message is processed
+        pub fn processed(msg_id: u128, destination: AccountId, first_seen: BlockNumber) -> Self {
+            RecvedMessage {
+                msg_id,
+                destination,
+                msg_vec: ink::prelude::vec![],
+                first_seen,
+                processed: true,
+                failed: false,
+                held: false,
+                inspection_count: 0,
+            }
+        }
+
         pub fn get_submitter_count(&self) -> u16 {
             let mut count: u16 = 0;
             for ele in self.msg_vec.iter() {
-                count += ele.get_submitter_count();
+                count = count.saturating_add(ele.get_submitter_count());
             }
 
             count
         }
 
-        pub fn contains(&self, router_id: u16) -> bool {
+        pub fn contains(&self, router: &AccountId) -> bool {
             for msg_ele in self.msg_vec.iter() {
                 for router_ele in msg_ele.submitters.iter() {
-                    if *router_ele == router_id {
+                    if router_ele == router {
                         return true;
                     }
                 }
@@ -100,30 +202,309 @@ mod algorithm {
         }
     }
 
-    #[derive(SpreadLayout, PackedLayout, Debug, Clone, scale::Encode, scale::Decode)]
+    #[derive(Debug, Clone, scale::Encode, scale::Decode)]
     #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo, StorageLayout))]
     pub struct VerifyInfo {
         cred_sum: u128,
-        submitters: ink_prelude::vec::Vec<u16>,
+        submitters: ink::prelude::vec::Vec<AccountId>,
     }
 
     #[ink(event)]
     pub struct VerifiedMessage {
         vf_passed: bool,
-        submitted: ink_prelude::vec::Vec<VerifyInfo>,
+        submitted: ink::prelude::vec::Vec<VerifyInfo>,
     }
 
     #[ink(event)]
     pub struct EvaluateResult {
-        behavior_type: ink_prelude::string::String,
-        results: ink_prelude::vec::Vec<u32>,
+        behavior_type: ink::prelude::string::String,
+        model: EvaluationModel,
+        results: ink::prelude::vec::Vec<u32>,
+    }
+
+    /// The credibility change of a router
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    pub struct CredibilityAdjustment {
+        router: AccountId,
+        honest: bool,
+        cred_before: u32,
+        cred_after: u32,
     }
 
-    #[derive(SpreadLayout, PackedLayout, Debug, scale::Encode, scale::Decode)]
+    /// Emitted when the routers delivering a message are evaluated according to the verification result
+    #[ink(event)]
+    pub struct CredibilityAdjusted {
+        #[ink(topic)]
+        from_chain: ink::prelude::string::String,
+        #[ink(topic)]
+        msg_id: u128,
+        model: EvaluationModel,
+        adjustments: ink::prelude::vec::Vec<CredibilityAdjustment>,
+    }
+
+    #[derive(Debug, scale::Encode, scale::Decode)]
     #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo, StorageLayout))]
     pub struct VerifiedCache {
         msg_id: u128,
-        submitted: ink_prelude::vec::Vec<VerifyInfo>,
+        submitted: ink::prelude::vec::Vec<VerifyInfo>,
+        vf_passed: bool,
+    }
+
+    /// The accepted copy of a verified message, kept as the reference for fraud proofs
+    #[derive(Debug, Clone, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo, StorageLayout))]
+    pub struct AcceptedMessage {
+        msg_hash: [u8;32],
+        // the routers who submitted the accepted copy, the submitters of the other copies have been evaluated as evil already
+        submitters: ink::prelude::vec::Vec<AccountId>,
+    }
+
+    /// A proven fraud of a router
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo, StorageLayout))]
+    pub struct SlashRecord {
+        challenger: AccountId,
+        accepted_hash: [u8;32],
+        fraud_hash: [u8;32],
+        cred_before: u32,
+        cred_after: u32,
+        block: BlockNumber,
+        /// The stake slashed by `Tokenomics`, `0` if `tokenomics` is not set or the call fails
+        stake_slashed: u128,
+    }
+
+    #[derive(Debug, PartialEq, Clone, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    pub enum FraudProofError {
+        /// The message has not been verified, or the verification did not pass
+        NotVerified,
+        UnknownRouter,
+        /// The router did not submit the accepted copy of the message
+        NotSubmitter,
+        /// The proven copy has the same hash as the accepted one
+        SameHash,
+        /// The signature is not signed by the router
+        InvalidSignature,
+        AlreadySlashed,
+    }
+
+    /// Emitted when a fraud proof is accepted
+    #[ink(event)]
+    pub struct RouterSlashed {
+        #[ink(topic)]
+        from_chain: ink::prelude::string::String,
+        #[ink(topic)]
+        msg_id: u128,
+        #[ink(topic)]
+        router: AccountId,
+        record: SlashRecord,
+    }
+
+    /// Emitted when a router registers, changes its status or metadata
+    #[ink(event)]
+    pub struct RouterUpdated {
+        #[ink(topic)]
+        router: AccountId,
+        info: RouterInfo,
+    }
+
+    /// Roles authorized to call administrative messages, the owner implicitly has all of them
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo, StorageLayout))]
+    pub enum Role {
+        /// Manages the system parameters and clears the storage
+        Admin,
+        /// Evaluates routers manually
+        Operator,
+        /// Announces messages before their copies are submitted, usually the protocol stack contract
+        Announcer,
+    }
+
+    #[derive(Debug, PartialEq, Clone, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    pub enum AdminError {
+        NotOwner,
+        /// The caller does not have the required role
+        Unauthorized,
+        /// The caller is not the pending owner, or there is no pending ownership transfer
+        NotPendingOwner,
+        ParamInvalid,
+        RouterNotExist,
+    }
+
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        previous: AccountId,
+        #[ink(topic)]
+        new: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct RoleChanged {
+        #[ink(topic)]
+        account: AccountId,
+        role: Role,
+        granted: bool,
+    }
+
+    /// The routers selected to deliver a message
+    #[derive(Debug, Clone, Default, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo, StorageLayout))]
+    pub struct MessageSelection {
+        routers: ink::prelude::vec::Vec<AccountId>,
+        // the extra routers selected for the inspection round
+        inspectors: ink::prelude::vec::Vec<AccountId>,
+        source: RandomnessSource,
+        // the seed the routers are selected from
+        seed: [u8; 32],
+        // the seed the inspection is drawn from
+        inspection_seed: Option<[u8; 32]>,
+    }
+
+    /// Emitted when routers are selected to deliver a message, or to inspect a held message
+    #[ink(event)]
+    pub struct RoutersSelected {
+        #[ink(topic)]
+        from_chain: ink::prelude::string::String,
+        #[ink(topic)]
+        msg_id: u128,
+        routers: ink::prelude::vec::Vec<AccountId>,
+        inspection: bool,
+        source: RandomnessSource,
+        /// the selection can be re-computed off-chain from the seed by `SeededRandom`
+        seed: [u8; 32],
+    }
+
+    /// The reasons why a submitted message copy is rejected
+    #[derive(Debug, PartialEq, Clone, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    pub enum SubmitError {
+        /// The caller is not a registered router
+        UnknownRouter,
+        /// The router is jailed or exiting
+        RouterInactive,
+        /// The message has been verified or finalized
+        AlreadyProcessed,
+        /// The router has submitted a copy of the message
+        DuplicateSubmission,
+        /// The router is not selected to deliver the message
+        NotSelected,
+        /// The message has not been announced by an `Announcer`, see `announce_message`
+        NotAnnounced,
+        /// The timeout of the message has passed, see `finalize_expired`
+        Expired,
+        /// The VRF output to select the routers has not been supplied, see `supply_vrf`,
+        /// or the runtime fails to provide the randomness
+        RandomnessNotReady,
+        /// The caller of `announce_message` does not have the `Announcer` role
+        Unauthorized,
+        /// The destination contract of the copy differs from the one of the announced message
+        WrongDestination,
+    }
+
+    /// The errors of the randomness sources
+    #[derive(Debug, PartialEq, Clone, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    pub enum RandomnessError {
+        /// The configured randomness source does not accept the call
+        WrongSource,
+        /// The caller is not an active registered router
+        NotRouter,
+        /// The router has a commitment not revealed yet
+        AlreadyCommitted,
+        NoCommitment,
+        /// The secret cannot be revealed in the same block as it is committed
+        TooEarly,
+        /// The revealed secret does not match the commitment, or the VRF output is not signed by the oracle
+        InvalidProof,
+        /// The VRF output is not supplied by the oracle account
+        NotOracle,
+        /// The `s` of the VRF signature is in the upper half of the curve order
+        HighS,
+        AlreadySupplied,
+        InvalidRound,
+    }
+
+    /// Emitted when a router reveals its secret, which is mixed into the beacon
+    #[ink(event)]
+    pub struct BeaconUpdated {
+        #[ink(topic)]
+        router: AccountId,
+        beacon: [u8; 32],
+    }
+
+    /// Emitted when the VRF output of a message is supplied
+    #[ink(event)]
+    pub struct RandomnessSupplied {
+        #[ink(topic)]
+        from_chain: ink::prelude::string::String,
+        #[ink(topic)]
+        msg_id: u128,
+        round: u8,
+        seed: [u8; 32],
+    }
+
+    /// The result of a submitted message copy
+    #[derive(Debug, PartialEq, Clone, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    pub enum SubmitOutcome {
+        Accepted,
+        /// Nothing is changed but the event `MessageRejected`, which is kept as the call does not fail
+        Rejected(SubmitError),
+    }
+
+    /// Emitted when a submitted message copy is rejected
+    #[ink(event)]
+    pub struct MessageRejected {
+        #[ink(topic)]
+        from_chain: ink::prelude::string::String,
+        #[ink(topic)]
+        msg_id: u128,
+        #[ink(topic)]
+        router: AccountId,
+        error: SubmitError,
+    }
+
+    /// Emitted when a pending message is finalized after its timeout
+    #[ink(event)]
+    pub struct MessageExpired {
+        #[ink(topic)]
+        from_chain: ink::prelude::string::String,
+        #[ink(topic)]
+        msg_id: u128,
+        /// the number of copies delivered before the timeout
+        copies: u16,
+        /// `true` if the message is marked as failed, otherwise it is verified with the delivered copies
+        failed: bool,
+    }
+
+    #[derive(Debug, PartialEq, Clone, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    pub enum FinalizeError {
+        NotExist,
+        AlreadyProcessed,
+        NotExpired,
+    }
+
+    /// Emitted when a message is held for an inspection round
+    #[ink(event)]
+    pub struct MessageHeld {
+        #[ink(topic)]
+        from_chain: ink::prelude::string::String,
+        #[ink(topic)]
+        msg_id: u128,
+        inspection_copy_count: u16,
+    }
+
+    /// Emitted when the inspection round of a held message ends
+    #[ink(event)]
+    pub struct MessageReleased {
+        #[ink(topic)]
+        from_chain: ink::prelude::string::String,
+        #[ink(topic)]
+        msg_id: u128,
         vf_passed: bool,
     }
 
@@ -134,59 +515,226 @@ mod algorithm {
     /// Add new fields to the below struct in order
     /// to add new static storage fields to your contract.
     #[ink(storage)]
-    #[derive(SpreadAllocate)]
+    // ink! resolves the storage keys of the `Mapping` fields into nested types
+    #[allow(clippy::type_complexity)]
     pub struct AlgorithmPrototype {
         /// Stores a single `bool` value on the storage.
         value: bool,
+        /// The owner of the contract
         account: AccountId,
+        /// The account to which the ownership is being transferred
+        pending_owner: Option<AccountId>,
+        roles: Mapping<(AccountId, Role), bool>,
         msg_copy_count: u16,
         vf_threshold: u128,
         coe_middle_cred: u32,
         coe_min_cred: u32,
         coe_max_cred: u32,
         coe_range_cred: u32,
+        /// The evaluation model in use
+        eval_params: EvaluationParams,
 
-        /// This type of storage needs to be optimized in product implementation
+        /// Router registry
+        /// The keys are indexed by `KeyIndex` as the storage cannot be iterated
         /// Follow this [issue: Allow iteration over contract storage #11410](https://github.com/paritytech/substrate/issues/11410#issuecomment-1156775111)
-        sim_router_keys: ink_prelude::vec::Vec<u16>,
-        sim_routers: ink_storage::Mapping<u16, SimNode>,
-
-        /// To be optimized
-        msg_v_keys: ink_prelude::vec::Vec<(ink_prelude::string::String, u128)>,
-        msg_2_verify: ink_storage::Mapping<(ink_prelude::string::String, u128), RecvedMessage>,
+        router_keys: KeyIndex<AccountId>,
+        routers: Mapping<AccountId, RouterInfo>,
+        /// The selection weights of the routers, kept in sync with `routers` by `save_router`
+        router_weights: WeightTree,
+        /// The `Tokenomics` contract providing the staking weights
+        tokenomics: Option<AccountId>,
+        weight_policy: WeightPolicy,
+        /// The fraction of the stake slashed in `Tokenomics` when a fraud proof is accepted, a factor of 10,000 was multiplied
+        slash_fraction: u32,
+
+        /// The provider of the selection seeds
+        randomness: RandomnessSource,
+        /// `CommitReveal`: the beacon mixed from the revealed secrets, and the commitments not revealed yet
+        beacon: [u8; 32],
+        commitments: Mapping<AccountId, ([u8; 32], BlockNumber)>,
+        /// `Vrf`: the compressed ECDSA public key of the oracle, and the supplied outputs of every message and round
+        vrf_key: Option<VrfKey>,
+        vrf_outputs: Mapping<((ink::prelude::string::String, u128), u8), [u8; 32]>,
+
+        msg_v_keys: KeyIndex<(ink::prelude::string::String, u128)>,
+        msg_2_verify: Mapping<(ink::prelude::string::String, u128), RecvedMessage>,
 
-        /// To be optimized
         /// Just for showing the result of the verification
-        cache_verified_keys: ink_prelude::vec::Vec<u128>,
-        cache_verified: ink_storage::Mapping<u128, VerifiedCache>,
+        cache_verified_keys: KeyIndex<u128>,
+        cache_verified: Mapping<u128, VerifiedCache>,
+
+        /// Verifiable Fraud Proof
+        accepted_msgs: Mapping<(ink::prelude::string::String, u128), AcceptedMessage>,
+        slash_records: Mapping<((ink::prelude::string::String, u128), AccountId), SlashRecord>,
+
+        /// The routers selected for each message
+        msg_selections: Mapping<(ink::prelude::string::String, u128), MessageSelection>,
+
+        /// Verifiable Passive Inspection
+        /// The probability with which a delivered message is held for inspection, a factor of 10,000 was multiplied
+        inspection_rate: u128,
+        /// The number of extra copies needed to finish an inspection round
+        inspection_copy_count: u16,
+
+        /// SQoS settings of the destination contracts
+        /// `msg_copy_count` and `vf_threshold` are used for the destinations without SQoS settings
+        sqos_profiles: Mapping<AccountId, SQoSProfile>,
+        /// The timeout for the destinations without SQoS settings, in blocks
+        msg_timeout: BlockNumber,
     }
 
     impl AlgorithmPrototype {
         /// Constructor that initializes the `bool` value to the given `init_value`.
         #[ink(constructor)]
         pub fn new(init_value: bool) -> Self {
-            ink_lang::utils::initialize_contract(|contract: &mut Self| {
-                contract.value = init_value;
-                contract.account = Self::env().caller();
-                contract.msg_copy_count = 5;
-                contract.vf_threshold = 7000;
-                contract.coe_middle_cred = 50;
-                contract.coe_min_cred = 0;
-                contract.coe_max_cred = 100;
-                contract.coe_range_cred = contract.coe_max_cred - contract.coe_min_cred;
-                contract.sim_router_keys = ink_prelude::vec![];
-                contract.msg_v_keys = ink_prelude::vec![];
-                contract.cache_verified_keys = ink_prelude::vec![];
-            })
+            let coe_min_cred = 0;
+            let coe_middle_cred = 50;
+            let coe_max_cred = 100;
+            let cred_range = CredibilityRange {
+                min: coe_min_cred,
+                middle: coe_middle_cred,
+                max: coe_max_cred,
+            };
+
+            Self {
+                value: init_value,
+                account: Self::env().caller(),
+                pending_owner: None,
+                roles: Mapping::default(),
+                msg_copy_count: 5,
+                vf_threshold: 7000,
+                coe_middle_cred,
+                coe_min_cred,
+                coe_max_cred,
+                coe_range_cred: cred_range.range(),
+                eval_params: EvaluationParams::piecewise(&cred_range),
+                router_keys: KeyIndex::default(),
+                routers: Mapping::default(),
+                router_weights: WeightTree::default(),
+                tokenomics: None,
+                slash_fraction: 1000,
+                weight_policy: WeightPolicy {
+                    cred_ratio: 10000,
+                    stake_ratio: 0,
+                    require_stake: false,
+                },
+                randomness: RandomnessSource::InkRandom,
+                beacon: [0; 32],
+                commitments: Mapping::default(),
+                vrf_key: None,
+                vrf_outputs: Mapping::default(),
+                msg_v_keys: KeyIndex::default(),
+                msg_2_verify: Mapping::default(),
+                cache_verified_keys: KeyIndex::default(),
+                cache_verified: Mapping::default(),
+                accepted_msgs: Mapping::default(),
+                slash_records: Mapping::default(),
+                msg_selections: Mapping::default(),
+                inspection_rate: 0,
+                inspection_copy_count: 2,
+                sqos_profiles: Mapping::default(),
+                msg_timeout: 100,
+            }
+        }
+
+        /// Start to transfer the ownership to `new_owner`, which takes effect after `new_owner` calls `accept_ownership`
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<(), AdminError> {
+            self.ensure_owner()?;
+
+            self.pending_owner = Some(new_owner);
+            Ok(())
+        }
+
+        /// The pending owner accepts the ownership
+        #[ink(message)]
+        pub fn accept_ownership(&mut self) -> Result<(), AdminError> {
+            let caller = Self::env().caller();
+            if self.pending_owner != Some(caller) {
+                return Err(AdminError::NotPendingOwner);
+            }
+
+            let previous = self.account;
+            self.account = caller;
+            self.pending_owner = None;
+
+            Self::env().emit_event(OwnershipTransferred {
+                previous,
+                new: caller,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn get_owner(&self) -> (AccountId, Option<AccountId>) {
+            (self.account, self.pending_owner)
+        }
+
+        #[ink(message)]
+        pub fn grant_role(&mut self, account: AccountId, role: Role) -> Result<(), AdminError> {
+            self.ensure_owner()?;
+
+            self.roles.insert((account, role), &true);
+
+            Self::env().emit_event(RoleChanged {
+                account,
+                role,
+                granted: true,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn revoke_role(&mut self, account: AccountId, role: Role) -> Result<(), AdminError> {
+            self.ensure_owner()?;
+
+            self.roles.remove((account, role));
+
+            Self::env().emit_event(RoleChanged {
+                account,
+                role,
+                granted: false,
+            });
+
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn has_role(&self, account: AccountId, role: Role) -> bool {
+            (account == self.account) || self.roles.contains((account, role))
+        }
+
+        fn ensure_owner(&self) -> Result<(), AdminError> {
+            if Self::env().caller() != self.account {
+                return Err(AdminError::NotOwner);
+            }
+
+            Ok(())
+        }
+
+        fn ensure_role(&self, role: Role) -> Result<(), AdminError> {
+            if !self.has_role(Self::env().caller(), role) {
+                return Err(AdminError::Unauthorized);
+            }
+
+            Ok(())
         }
 
         #[ink(message)]
         /// Set the system parameters
-        pub fn set_sysinfo(&mut self, msg_copy_count: u16, vf_t: u128) {
-            // just for test without account validation 
+        pub fn set_sysinfo(&mut self, msg_copy_count: u16, vf_t: u128) -> Result<(), AdminError> {
+            self.ensure_role(Role::Admin)?;
+
+            if (msg_copy_count == 0) || (vf_t > 10000) {
+                return Err(AdminError::ParamInvalid);
+            }
+
             self.value = !self.value;
             self.msg_copy_count = msg_copy_count;
             self.vf_threshold = vf_t;
+            Ok(())
         }
 
         #[ink(message)]
@@ -194,12 +742,93 @@ mod algorithm {
         pub fn get_sysinfo(&self) -> (bool, u16, u128) {
             (self.value, self.msg_copy_count, self.vf_threshold)
         }
+
+        #[ink(message)]
+        /// Set the timeout for the messages sent to the destinations without SQoS settings, in blocks
+        pub fn set_msg_timeout(&mut self, timeout: BlockNumber) -> Result<(), AdminError> {
+            self.ensure_role(Role::Admin)?;
+
+            if timeout == 0 {
+                return Err(AdminError::ParamInvalid);
+            }
+
+            self.msg_timeout = timeout;
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn get_msg_timeout(&self) -> BlockNumber {
+            self.msg_timeout
+        }
+
+        #[ink(message)]
+        /// Register the SQoS settings of the caller, which is the destination contract of the messages.
+        /// Returns `false` if the settings are invalid.
+        pub fn set_sqos(&mut self, profile: SQoSProfile) -> bool {
+            if !profile.is_valid() {
+                return false;
+            }
+
+            self.sqos_profiles.insert(Self::env().caller(), &profile);
+            true
+        }
+
+        #[ink(message)]
+        /// Remove the SQoS settings of the caller, then the system parameters apply to the messages sent to it
+        pub fn remove_sqos(&mut self) {
+            self.sqos_profiles.remove(Self::env().caller());
+        }
+
+        #[ink(message)]
+        /// Returns the SQoS settings applied to the messages sent to `destination`
+        pub fn get_sqos(&self, destination: AccountId) -> SQoSProfile {
+            self.sqos_of(&destination)
+        }
+
+        /// The SQoS settings of `destination`, or the ones derived from the system parameters if not registered
+        fn sqos_of(&self, destination: &AccountId) -> SQoSProfile {
+            if let Some(profile) = self.sqos_profiles.get(destination) {
+                profile
+            } else {
+                SQoSProfile {
+                    copy_count: self.msg_copy_count,
+                    vf_threshold: self.vf_threshold,
+                    timeout: self.msg_timeout,
+                    allow_single_copy: self.msg_copy_count <= 1,
+                    // no partial verification
+                    min_partial_copies: self.msg_copy_count,
+                }
+            }
+        }
+
+        #[ink(message)]
+        /// Set the parameters of Verifiable Passive Inspection
+        /// 
+        /// `rate` is the probability with which a delivered message is held for inspection, a factor of 10,000 was multiplied.
+        /// `copy_count` is the number of extra copies submitted by inspectors to finish an inspection round
+        pub fn set_inspection_params(&mut self, rate: u128, copy_count: u16) -> Result<(), AdminError> {
+            self.ensure_role(Role::Admin)?;
+
+            if (rate > 10000) || (copy_count == 0) {
+                return Err(AdminError::ParamInvalid);
+            }
+
+            self.inspection_rate = rate;
+            self.inspection_copy_count = copy_count;
+            Ok(())
+        }
+
+        #[ink(message)]
+        /// Returns the parameters of Verifiable Passive Inspection
+        pub fn get_inspection_params(&self) -> (u128, u16) {
+            (self.inspection_rate, self.inspection_copy_count)
+        }
     
         #[ink(message)]
         /// Simulation to the simplest version of the routers selection algoritm in Dante protocol
         /// 
-        /// Call `random_register_routers` to add some simulation routers with fixed credibility, 
-        /// which will be dynamically adjusted by *router evaluation* algorithm in product implementation.
+        /// Call `register_router` to register routers, whose credibilities will be dynamically adjusted by *router evaluation* algorithm.
+        /// Only active routers are taken into account.
         /// 
         /// `create_intervals` is part of router selection algorithm
         /// 
@@ -207,326 +836,872 @@ mod algorithm {
         /// 
         /// `selection_statistic` provides an intuitive validation of the 'Probability distribution' results of the router selection algorithm
         /// parameter `n` is the number of select times
-        pub fn create_intervals(&self, just_for_test: bool) -> ink_prelude::vec::Vec<SelectionInterval>{
-            let mut sum: u32 = 0;
-            let mut select_intervals = ink_prelude::vec![];
-            for router_key in self.sim_router_keys.iter() {
-                if let Some(router) = self.sim_routers.get(router_key) {
+        pub fn create_intervals(&self, _just_for_test: bool) -> ink::prelude::vec::Vec<SelectionInterval>{
+            let mut sum: u128 = 0;
+            let mut select_intervals = ink::prelude::vec![];
+            for router_key in self.router_keys.iter() {
+                if let Some(router) = self.routers.get(router_key) {
+                    // only active routers can be selected
+                    if !router.is_active() {
+                        continue;
+                    }
+
+                    let weight = self.router_weights.weight_of(&router_key);
                     select_intervals.push(SelectionInterval{
-                        id: router.0,
-                        cre: router.1,
+                        id: router_key,
+                        cre: router.credibility,
+                        weight,
                         low: sum,
-                        high: sum + router.1,
+                        high: sum.saturating_add(weight),
                         selected: 0,
                     });
-                    sum += router.1;
+                    sum = sum.saturating_add(weight);
                 } 
             }
 
             select_intervals
         }
 
-        /// Test selection algorithm
-        /// test interface for register
+        /// Register the caller as a router
+        /// 
+        /// A new router starts with the middle credibility, which will be dynamically adjusted by *router evaluation* algorithm.
         #[ink(message)]
-        pub fn random_register_routers(&mut self, routers: ink_prelude::vec::Vec<u32>) {
-            let mut start_id = self.sim_router_keys.len() as u16;
-            for ele in routers {
-                self.sim_router_keys.push(start_id);
-                self.sim_routers.insert(&start_id, &SimNode(start_id, ele));
-                start_id += 1;
+        pub fn register_router(&mut self, metadata: ink::prelude::string::String) -> bool {
+            let caller = Self::env().caller();
+            if self.routers.contains(caller) {
+                return false;
             }
+
+            let info = RouterInfo {
+                credibility: self.coe_middle_cred,
+                registered_at: Self::env().block_number(),
+                status: RouterStatus::Active,
+                metadata,
+                offences: 0,
+                last_offence: None,
+            };
+
+            self.router_keys.insert(&caller);
+            self.save_router(&caller, &info);
+
+            Self::env().emit_event(RouterUpdated {
+                router: caller,
+                info,
+            });
+
+            true
+        }
+
+        /// Update the metadata of the caller
+        #[ink(message)]
+        pub fn update_router_metadata(&mut self, metadata: ink::prelude::string::String) -> bool {
+            let caller = Self::env().caller();
+            if let Some(mut info) = self.routers.get(caller) {
+                info.metadata = metadata;
+                self.save_router(&caller, &info);
+
+                Self::env().emit_event(RouterUpdated {
+                    router: caller,
+                    info,
+                });
+
+                true
+            } else {
+                false
+            }
+        }
+
+        /// The caller starts to leave the network, and will not be selected or submit message copies any more
+        #[ink(message)]
+        pub fn exit_router(&mut self) -> bool {
+            let caller = Self::env().caller();
+            if let Some(mut info) = self.routers.get(caller) {
+                info.status = RouterStatus::Exiting;
+                self.save_router(&caller, &info);
+
+                Self::env().emit_event(RouterUpdated {
+                    router: caller,
+                    info,
+                });
+
+                true
+            } else {
+                false
+            }
+        }
+
+        #[ink(message)]
+        pub fn get_router(&self, router: AccountId) -> Option<RouterInfo> {
+            self.routers.get(router)
         }
 
+        /// Get at most `limit` registered routers starting from `cursor`
+        /// Returns the routers and the cursor of the next page, which is `None` if there are no more routers
         #[ink(message)]
-        pub fn get_registered_routers(&self, flag: bool) -> ink_prelude::vec::Vec<SimNode> {
-            let mut reg_routers = ink_prelude::vec![];
-            for ele in self.sim_router_keys.iter() {
-                if let Some(router) = self.sim_routers.get(ele) {
-                    reg_routers.push(router);
+        pub fn get_registered_routers(&self, cursor: Option<u64>, limit: u32) -> (ink::prelude::vec::Vec<(AccountId, RouterInfo)>, Option<u64>) {
+            let (keys, next) = self.router_keys.page(cursor, limit);
+            let mut reg_routers = ink::prelude::vec![];
+            for ele in keys {
+                if let Some(router) = self.routers.get(ele) {
+                    reg_routers.push((ele, router));
                 }
             }
 
-            reg_routers
+            (reg_routers, next)
         }
 
+        /// Remove at most `limit` routers, returns the number of the routers left
         #[ink(message)]
-        pub fn clear_routers(&mut self) {
-            for ele in self.sim_router_keys.iter() {
-                    self.sim_routers.remove(ele);
+        pub fn clear_routers(&mut self, limit: u32) -> Result<u64, AdminError> {
+            self.ensure_role(Role::Admin)?;
+
+            for ele in self.router_keys.pop_front(limit) {
+                self.routers.remove(ele);
+                self.router_weights.set(&ele, 0);
             }
 
-            self.sim_router_keys.clear();
+            Ok(self.router_keys.len())
         }
 
         /// selection statistic
         /// This provides an intuitive validation of the 'Probability distribution' results of the router selection algorithm
         /// Prameter@n: Sampling times
         #[ink(message)]
-        pub fn selection_statistic(&self, n: u16) -> Option<ink_prelude::vec::Vec<SelectionInterval>>{
-            let mut start_idx: u16 = 0;
+        pub fn selection_statistic(&self, n: u16) -> Option<ink::prelude::vec::Vec<SelectionInterval>>{
             let mut select_intervals = self.create_intervals(true);
+            let weights: ink::prelude::vec::Vec<u128> = select_intervals.iter().map(|ele| ele.weight).collect();
+            let mut rng = SeededRandom::new(&Self::env_seed(&[])?, DOMAIN_ROUTERS);
 
-            if select_intervals.len() == 0 {
-                return None;
+            for _ in 0..n {
+                let idx = selection::weighted_sample(&weights, &mut rng)?;
+                select_intervals[idx].selected += 1;
             }
 
-            let mut selected = 0;
+            Some(select_intervals)
+        }
 
-            while selected < n {
-                let start_seed = u16::to_be_bytes(start_idx);
-                let random_seed = ink_env::random::<ink_env::DefaultEnvironment>(&start_seed).unwrap().0;
-                let mut seed_idx = 0;
+        /// Test selection algorithm
+        /// this will randomly choose `n` routers according to their credibility, with the seed from the `FetchRandom` chain extension
+        #[ink(message)]
+        pub fn selection_test(&self, n: u16) -> Option<ink::prelude::vec::Vec<AccountId>>{
+            let selected = self.select_routers(n, &[], &Self::env_seed(&[])?, DOMAIN_ROUTERS);
+            if (selected.len() as u16) < n {
+                return None;
+            }
 
-                while seed_idx < (random_seed.as_ref().len() - 1) {
-                    let two_bytes: [u8; 2] = random_seed.as_ref()[seed_idx..seed_idx+2].try_into().unwrap();
-                    let rand_num = u16::from_be_bytes(two_bytes) as u32;
+            Some(selected)
+        }
 
-                    let max = select_intervals[select_intervals.len() - 1].high;
+        /// Randomly choose at most `n` active routers except `excluded` according to their selection weights, without replacement
+        /// The random numbers are expanded from `seed` in `domain`
+        fn select_routers(&self, n: u16, excluded: &[AccountId], seed: &[u8; 32], domain: u8) -> ink::prelude::vec::Vec<AccountId> {
+            let mut rng = SeededRandom::new(seed, domain);
+            self.router_weights.sample(n as usize, excluded, &mut rng)
+        }
 
-                    // rand_num will multiple 100 in later implementation as the credibility does
-                    let rand_num = rand_num % max;
+        /// The seed from the `FetchRandom` chain extension
+        /// Returns `None` if the runtime fails to provide the randomness
+        fn env_seed(subject: &[u8]) -> Option<[u8; 32]> {
+            Self::env().extension().fetch_random(subject.to_vec()).ok()
+        }
 
-                    for ele in select_intervals.iter_mut() {
-                        if ele.contains(rand_num) {
-                            selected += 1;
-                            ele.selected += 1;
-                            break;
-                        }
-                    }
+        /// The seed of `round` of the message `key` from the configured randomness source
+        /// Returns `None` if the VRF output has not been supplied, or the runtime fails to provide the randomness
+        fn provider_seed(&self, key: &(ink::prelude::string::String, u128), round: u8) -> Option<[u8; 32]> {
+            let mut subject = scale::Encode::encode(key);
+            subject.push(round);
 
-                    if selected >= n {
-                        return Some(select_intervals);
-                    }
+            match self.randomness {
+                RandomnessSource::InkRandom => Self::env_seed(&subject),
+                RandomnessSource::CommitReveal => Some(randomness::mix(&self.beacon, &subject)),
+                RandomnessSource::Vrf => self.vrf_outputs.get(&(key.clone(), round)),
+            }
+        }
 
-                    seed_idx += 2;
-                }
+        /// Set the randomness source of the selection, `vrf_key` is the compressed ECDSA public key of the VRF oracle
+        #[ink(message)]
+        pub fn set_randomness_source(&mut self, source: RandomnessSource, vrf_key: Option<[u8; 33]>) -> Result<(), AdminError> {
+            self.ensure_role(Role::Admin)?;
 
-                start_idx += 1;
+            if (source == RandomnessSource::Vrf) && vrf_key.is_none() {
+                return Err(AdminError::ParamInvalid);
             }
 
-            Some(select_intervals)
+            self.randomness = source;
+            self.vrf_key = vrf_key.map(VrfKey);
+            Ok(())
         }
 
-        /// Test selection algorithm
-        /// this will randomly choose `n` routers according to their credibility
+        /// Returns the randomness source, the VRF oracle key, and the beacon of commit-reveal
         #[ink(message)]
-        pub fn selection_test(&self, n: u16) -> Option<ink_prelude::vec::Vec<u16>>{
-            let mut start_idx = 0;
-            let mut select_intervals = self.create_intervals(true);
-            if (select_intervals.len() as u16) < n {
-                return None;
+        pub fn get_randomness_source(&self) -> (RandomnessSource, Option<[u8; 33]>, [u8; 32]) {
+            (self.randomness, self.vrf_key.map(|key| key.0), self.beacon)
+        }
+
+        /// Commit-reveal: an active router commits `blake2x256(secret)`, and reveals the secret in a later block
+        #[ink(message)]
+        pub fn commit_randomness(&mut self, commitment: [u8; 32]) -> Result<(), RandomnessError> {
+            let caller = Self::env().caller();
+            if self.randomness != RandomnessSource::CommitReveal {
+                return Err(RandomnessError::WrongSource);
             }
 
-            let mut selected: ink_prelude::vec::Vec<u16> = ink_prelude::vec![];
-            while (selected.len() as u16) < n {
-                let random_seed = ink_env::random::<ink_env::DefaultEnvironment>(&[start_idx]).unwrap().0;
-                let mut seed_idx = 0;
+            if !self.routers.get(caller).is_some_and(|router| router.is_active()) {
+                return Err(RandomnessError::NotRouter);
+            }
 
-                while seed_idx < (random_seed.as_ref().len() - 1) {
-                    let two_bytes: [u8; 2] = random_seed.as_ref()[seed_idx..seed_idx+2].try_into().unwrap();
-                    let rand_num = u16::from_be_bytes(two_bytes) as u32;
+            if self.commitments.contains(caller) {
+                return Err(RandomnessError::AlreadyCommitted);
+            }
 
-                    let max = select_intervals[select_intervals.len() - 1].high;
+            self.commitments.insert(caller, &(commitment, Self::env().block_number()));
+            Ok(())
+        }
 
-                    // rand_num will multiple 100 in later implementation as the credibility does
-                    let rand_num = rand_num % max;
+        /// Commit-reveal: reveal the committed secret, which is mixed into the beacon
+        #[ink(message)]
+        pub fn reveal_randomness(&mut self, secret: [u8; 32]) -> Result<(), RandomnessError> {
+            let caller = Self::env().caller();
+            if self.randomness != RandomnessSource::CommitReveal {
+                return Err(RandomnessError::WrongSource);
+            }
 
-                    let mut choose_next = false;
-                    for ele in select_intervals.iter_mut() {
-                        if ele.contains(rand_num) {
-                            if ele.selected == 0 {
-                                selected.push(ele.id);
-                                ele.selected += 1;
-                                break;
-                            } else {
-                                choose_next = true;
-                            }
-                        }
+            let (commitment, committed_at) = self.commitments.get(caller).ok_or(RandomnessError::NoCommitment)?;
+            if Self::env().block_number() <= committed_at {
+                return Err(RandomnessError::TooEarly);
+            }
 
-                        if choose_next && (ele.selected == 0) {
-                            selected.push(ele.id);
-                            ele.selected += 1;
-                            break;
-                        }
-                    }
+            if randomness::commitment(&secret) != commitment {
+                return Err(RandomnessError::InvalidProof);
+            }
 
-                    if (selected.len() as u16) >= n {
-                        return Some(selected);
-                    }
+            self.commitments.remove(caller);
+            self.beacon = randomness::mix(&self.beacon, &secret);
 
-                    seed_idx += 2;
-                }
+            Self::env().emit_event(BeaconUpdated {
+                router: caller,
+                beacon: self.beacon,
+            });
 
-                start_idx += 1;
+            Ok(())
+        }
+
+        /// VRF: supply the output of `round` of a message, which is the low-S ECDSA signature of the oracle
+        /// over `blake2x256(scale::Encode((from_chain, msg_id), round))`. The seed is `blake2x256(signature)`.
+        /// Only the oracle account, `blake2x256` of its compressed public key, can supply the outputs.
+        ///
+        /// The signature is not a VRF proof: the oracle picks the nonce of every signature,
+        /// so it can try many of them and supply the one whose seed selects the routers it prefers.
+        /// The source is as fair as the oracle is honest.
+        /// 
+        /// `round` `0` is to select the routers, which must be supplied before the message is announced.
+        /// `round` `1` is to draw the inspection, the delivered message waits for it if it is not supplied yet.
+        #[ink(message)]
+        pub fn supply_vrf(&mut self, from_chain: ink::prelude::string::String, msg_id: u128, round: u8, signature: [u8; 65]) -> Result<(), RandomnessError> {
+            if self.randomness != RandomnessSource::Vrf {
+                return Err(RandomnessError::WrongSource);
             }
 
-            Some(selected)
+            let vrf_key = self.vrf_key.ok_or(RandomnessError::WrongSource)?;
+            let mut oracle = <ink::env::hash::Blake2x256 as ink::env::hash::HashOutput>::Type::default();
+            ink::env::hash_encoded::<ink::env::hash::Blake2x256, _>(&vrf_key.0, &mut oracle);
+            if Self::env().caller() != AccountId::from(oracle) {
+                return Err(RandomnessError::NotOracle);
+            }
+
+            if (round != ROUND_SELECTION) && (round != ROUND_INSPECTION) {
+                return Err(RandomnessError::InvalidRound);
+            }
+
+            let key = (from_chain, msg_id);
+            if self.vrf_outputs.contains(&(key.clone(), round)) {
+                return Err(RandomnessError::AlreadySupplied);
+            }
+
+            if !randomness::is_low_s(&signature) {
+                return Err(RandomnessError::HighS);
+            }
+
+            let message = randomness::blake2(&scale::Encode::encode(&(&key, round)));
+            let mut recovered = [0; 33];
+            if ink::env::ecdsa_recover(&signature, &message, &mut recovered).is_err() || (VrfKey(recovered) != vrf_key) {
+                return Err(RandomnessError::InvalidProof);
+            }
+
+            let seed = randomness::blake2(&signature);
+            self.vrf_outputs.insert(&(key.clone(), round), &seed);
+
+            Self::env().emit_event(RandomnessSupplied {
+                from_chain: key.0.clone(),
+                msg_id: key.1,
+                round,
+                seed,
+            });
+
+            // the delivered message waiting for the randomness of the inspection
+            if round == ROUND_INSPECTION {
+                if let Some(msg_instance) = self.msg_2_verify.get(&key) {
+                    let selection = self.msg_selections.get(&key).unwrap_or_default();
+                    if !msg_instance.processed && msg_instance.held && selection.inspectors.is_empty() {
+                        self.start_inspection(&key, msg_instance, selection, seed);
+                    }
+                }
+            }
+
+            Ok(())
+        }
+
+        /// Save `router` and update its selection weight
+        fn save_router(&mut self, router_id: &AccountId, router: &RouterInfo) {
+            self.routers.insert(router_id, router);
+
+            let weight = self.selection_weight(router_id, router);
+            self.router_weights.set(router_id, weight);
+        }
+
+        /// The selection weight of `router` according to the `weight_policy`, only active routers can be selected
+        fn selection_weight(&self, router_id: &AccountId, router: &RouterInfo) -> u128 {
+            if !router.is_active() {
+                return 0;
+            }
+
+            let policy = &self.weight_policy;
+            let stake_weight = if policy.require_stake || (policy.stake_ratio > 0) {
+                self.staking_weight(router_id)
+            } else {
+                0
+            };
+
+            if policy.require_stake && (stake_weight == 0) {
+                return 0;
+            }
+
+            // the weight is capped by `WeightTree` anyway
+            (router.credibility as u128)
+                .saturating_mul(policy.cred_ratio as u128)
+                .saturating_add(stake_weight.saturating_mul(policy.stake_ratio as u128))
+        }
+
+        /// Query the staking weight of `router_id` from `Tokenomics::get_staking_weights`
+        /// Returns `0` if `tokenomics` is not set, the router has not staked, or the call fails
+        fn staking_weight(&self, router_id: &AccountId) -> u128 {
+            let tokenomics = match self.tokenomics {
+                Some(tokenomics) => tokenomics,
+                None => return 0,
+            };
+
+            let rst = ink::env::call::build_call::<Environment>()
+                .call_type(ink::env::call::Call::new(tokenomics).gas_limit(0))
+                .exec_input(
+                    ink::env::call::ExecutionInput::new(ink::env::call::Selector::new(ink::selector_bytes!("get_staking_weights")))
+                        .push_arg(router_id)
+                )
+                .returns::<Option<u128>>()
+                .try_invoke();
+
+            match rst {
+                Ok(Ok(Some(weight))) => weight,
+                _ => 0,
+            }
+        }
+
+        /// Set the `Tokenomics` contract providing the staking weights, `None` to select routers by credibility only
+        /// The cached weights are updated by `refresh_weights`
+        #[ink(message)]
+        pub fn set_tokenomics(&mut self, tokenomics: Option<AccountId>) -> Result<(), AdminError> {
+            self.ensure_role(Role::Admin)?;
+
+            self.tokenomics = tokenomics;
+            Ok(())
+        }
+
+        /// Set how the selection weight is mixed from the credibility and the staking weight
+        /// The cached weights are updated by `refresh_weights`
+        #[ink(message)]
+        pub fn set_weight_policy(&mut self, policy: WeightPolicy) -> Result<(), AdminError> {
+            self.ensure_role(Role::Admin)?;
+
+            if !policy.is_valid() {
+                return Err(AdminError::ParamInvalid);
+            }
+
+            self.weight_policy = policy;
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn get_weight_policy(&self) -> (Option<AccountId>, WeightPolicy) {
+            (self.tokenomics, self.weight_policy.clone())
+        }
+
+        /// Set the fraction of the stake slashed in `Tokenomics` when a fraud proof is accepted, a factor of 10,000 was multiplied
+        #[ink(message)]
+        pub fn set_slash_fraction(&mut self, fraction: u32) -> Result<(), AdminError> {
+            self.ensure_role(Role::Admin)?;
+
+            if fraction > 10000 {
+                return Err(AdminError::ParamInvalid);
+            }
+
+            self.slash_fraction = fraction;
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn get_slash_fraction(&self) -> u32 {
+            self.slash_fraction
+        }
+
+        /// Record the verified delivery of `router_id` with its `credibility` in the current epoch of `Tokenomics`
+        /// Nothing is recorded if `tokenomics` is not set or the call fails, which does not affect the verification
+        fn record_delivery(&self, router_id: &AccountId, credibility: u32) {
+            let tokenomics = match self.tokenomics {
+                Some(tokenomics) => tokenomics,
+                None => return,
+            };
+
+            // `StakingError` has no fields, so it is decoded as its index
+            let _ = ink::env::call::build_call::<Environment>()
+                .call_type(ink::env::call::Call::new(tokenomics).gas_limit(0))
+                .exec_input(
+                    ink::env::call::ExecutionInput::new(ink::env::call::Selector::new(ink::selector_bytes!("record_delivery")))
+                        .push_arg(router_id)
+                        .push_arg(credibility)
+                )
+                .returns::<Result<(), u8>>()
+                .try_invoke();
+        }
+
+        /// Slash `slash_fraction` of the stake of `router_id` in `Tokenomics`, the reporter's share goes to `challenger`
+        /// Returns the amount slashed, `0` if `tokenomics` is not set or the call fails, which does not affect the fraud proof
+        fn slash_stake(&self, router_id: &AccountId, challenger: &AccountId) -> u128 {
+            let tokenomics = match self.tokenomics {
+                Some(tokenomics) => tokenomics,
+                None => return 0,
+            };
+
+            // `StakingError` has no fields, so it is decoded as its index
+            let rst = ink::env::call::build_call::<Environment>()
+                .call_type(ink::env::call::Call::new(tokenomics).gas_limit(0))
+                .exec_input(
+                    ink::env::call::ExecutionInput::new(ink::env::call::Selector::new(ink::selector_bytes!("slash")))
+                        .push_arg(router_id)
+                        .push_arg(TokenomicsSlashAmount::Fraction(self.slash_fraction))
+                        .push_arg(SLASH_REASON_FRAUD_PROOF)
+                        .push_arg(Some(*challenger))
+                )
+                .returns::<Result<u128, u8>>()
+                .try_invoke();
+
+            match rst {
+                Ok(Ok(Ok(slashed))) => slashed,
+                _ => 0,
+            }
+        }
+
+        /// Get at most `limit` routers and their cached selection weights in the order the routers are drawn,
+        /// starting from the slot `from`, which starts at `1`.
+        /// With these and the seed in `RoutersSelected`, the selection can be replayed off-chain
+        #[ink(message)]
+        pub fn get_selection_weights(&self, from: u64, limit: u32) -> ink::prelude::vec::Vec<(AccountId, u128)> {
+            self.router_weights.slots(from, limit)
+        }
+
+        /// Recompute the selection weights of at most `limit` routers starting from `cursor`.
+        /// The staking weights change in `Tokenomics` without notifying this contract,
+        /// so anyone can call this to bring the cached weights up to date.
+        /// Returns the cursor of the next page, which is `None` if there are no more routers
+        #[ink(message)]
+        pub fn refresh_weights(&mut self, cursor: Option<u64>, limit: u32) -> Option<u64> {
+            let (keys, next) = self.router_keys.page(cursor, limit);
+            for router_id in keys.iter() {
+                if let Some(router) = self.routers.get(router_id) {
+                    let weight = self.selection_weight(router_id, &router);
+                    self.router_weights.set(router_id, weight);
+                }
+            }
+
+            next
+        }
+
+        /// Announce a message to be delivered, the routers to deliver it are selected according to their credibility 
+        /// and the SQoS settings of `destination`. Only the selected routers can submit copies of the message.
+        /// 
+        /// Only the `Announcer` can announce messages, as the SQoS settings of `destination` decide how the message is verified.
+        /// The copies of a message not announced yet are rejected with `NotAnnounced`.
+        /// Returns the selected routers
+        #[ink(message)]
+        pub fn announce_message(&mut self, from_chain: ink::prelude::string::String, msg_id: u128, destination: AccountId) -> Result<ink::prelude::vec::Vec<AccountId>, SubmitError> {
+            if !self.has_role(Self::env().caller(), Role::Announcer) {
+                return Err(SubmitError::Unauthorized);
+            }
+
+            let key = (from_chain, msg_id);
+
+            if let Some(msg_instance) = self.msg_2_verify.get(&key) {
+                if msg_instance.processed {
+                    return Err(SubmitError::AlreadyProcessed);
+                }
+            } else {
+                self.announce(&key, destination)?;
+            }
+
+            Ok(self.msg_selections.get(&key).unwrap_or_default().routers)
+        }
+
+        /// Get the routers selected to deliver the message and to inspect it
+        #[ink(message)]
+        pub fn get_selection(&self, from_chain: ink::prelude::string::String, msg_id: u128) -> Option<MessageSelection> {
+            self.msg_selections.get(&(from_chain, msg_id))
+        }
+
+        /// Create the pending message and select the routers to deliver it
+        fn announce(&mut self, key: &(ink::prelude::string::String, u128), destination: AccountId) -> Result<(), SubmitError> {
+            let seed = self.provider_seed(key, ROUND_SELECTION).ok_or(SubmitError::RandomnessNotReady)?;
+
+            let msg_instance = RecvedMessage{
+                msg_id: key.1,
+                destination,
+                msg_vec: ink::prelude::vec![],
+                first_seen: Self::env().block_number(),
+                processed: false,
+                failed: false,
+                held: false,
+                inspection_count: 0,
+            };
+
+            let sqos = self.sqos_of(&destination);
+            let routers = self.select_routers(sqos.copy_count, &[], &seed, DOMAIN_ROUTERS);
+
+            let selection = MessageSelection {
+                routers,
+                inspectors: ink::prelude::vec![],
+                source: self.randomness,
+                seed,
+                inspection_seed: None,
+            };
+
+            self.msg_selections.insert(key, &selection);
+            self.msg_2_verify.insert(key, &msg_instance);
+            self.msg_v_keys.insert(key);
+
+            Self::env().emit_event(RoutersSelected {
+                from_chain: key.0.clone(),
+                msg_id: key.1,
+                routers: selection.routers,
+                inspection: false,
+                source: selection.source,
+                seed: selection.seed,
+            });
+
+            Ok(())
         }
 
         /// simulation of message verification
         /// 
-        /// In this simulation, we do not limit the number of message copies to verify a message. 
-        /// And the number determines how many routers one message needs to be delivered parallelly, 
-        /// this will be configured by users through SQoS settings in the product implementation.
-        /// At that time, when enough copies have been delivered, `simu_message_verification` will be called dynamically.
+        /// The number of message copies to verify a message determines how many routers one message needs to be delivered parallelly, 
+        /// which is configured by the destination contract through SQoS settings, see `set_sqos`.
+        /// When enough copies have been delivered, `simu_message_verification` will be called dynamically.
         /// 
         /// `simu_submit_message` simulates the submittion of delivered message copies
         /// Message copies belong to the same message only if they have the same `IReceivedMessage::id` and `IReceivedMessage::from_chain` 
         /// 
-        /// #param@router_id: this is a parameter just for test. In product implementation, this will be `Self::env().caller()`
-        /// 
+        /// The caller must be an active registered router, otherwise `UnknownRouter` or `RouterInactive` is returned as an error.
+        /// If the copy is rejected for any other reason, `SubmitOutcome::Rejected` is returned and an event `MessageRejected`
+        /// is emitted with the same error, such that relayers can be alerted. The call does not fail in this case,
+        /// as an error would revert the event with it
         #[ink(message)]
-        pub fn simu_submit_message(&mut self, recv_msg: super::IReceivedMessage, router_id: u16) {
-            // `router_id` validation
-            if !self.sim_routers.contains(router_id) {
-                return;
-            }
-
+        pub fn simu_submit_message(&mut self, recv_msg: super::IReceivedMessage) -> Result<SubmitOutcome, SubmitError> {
+            let router_id = Self::env().caller();
             let key = (recv_msg.from_chain.clone(), recv_msg.id);
 
-            if let Some(mut msg_instance) = self.msg_2_verify.get(&key) {
-                // check whether the related message is out of time
-                if msg_instance.processed {
-                    return;
-                }
+            match self.submit_message_copy(recv_msg, router_id) {
+                Ok(()) => Ok(SubmitOutcome::Accepted),
+                Err(error @ (SubmitError::UnknownRouter | SubmitError::RouterInactive)) => Err(error),
+                Err(error) => {
+                    Self::env().emit_event(MessageRejected {
+                        from_chain: key.0,
+                        msg_id: key.1,
+                        router: router_id,
+                        error: error.clone(),
+                    });
 
-                // check submit once
-                if msg_instance.contains(router_id) {
-                    return;
+                    Ok(SubmitOutcome::Rejected(error))
                 }
+            }
+        }
 
-                let msg_hash = recv_msg.into_hash::<ink_env::hash::Keccak256>();
-                let mut hash_found = false;
+        /// Nothing is written before the copy is accepted, so a rejected copy changes nothing
+        fn submit_message_copy(&mut self, recv_msg: super::IReceivedMessage, router_id: AccountId) -> Result<(), SubmitError> {
+            // router validation
+            let router = self.routers.get(router_id).ok_or(SubmitError::UnknownRouter)?;
+            if !router.is_active() {
+                return Err(SubmitError::RouterInactive);
+            }
 
-                for ele in msg_instance.msg_vec.iter_mut() {
-                    if ele.msg_hash == msg_hash {
-                        ele.submitters.push(router_id);
-                        hash_found = true;
-                        break;
-                    }
-                }
+            let key = (recv_msg.from_chain.clone(), recv_msg.id);
 
-                if !hash_found {
-                    let mut msg_info = MessageInfo {
-                        msg_hash: msg_hash,
-                        msg_detail: recv_msg.into_bytes(),
-                        submitters: ink_prelude::vec![],
-                    };
-                    msg_info.submitters.push(router_id);
-                    msg_instance.msg_vec.push(msg_info);
-                }
+            // the destination is the one the message is announced with, never the one claimed by the copy
+            let mut msg_instance = self.msg_2_verify.get(&key).ok_or(SubmitError::NotAnnounced)?;
 
-                // we comment off the following lines to manually call `simu_message_verification` for simulation
-                if msg_instance.get_submitter_count() >= self.msg_copy_count {
-                    // self.msg_2_verify.remove(&key);
+            // check whether the related message is out of time
+            if msg_instance.processed {
+                return Err(SubmitError::AlreadyProcessed);
+            }
 
-                    self.simu_message_verification(&msg_instance);
+            // check submit once
+            if msg_instance.contains(&router_id) {
+                return Err(SubmitError::DuplicateSubmission);
+            }
 
-                    let msg_processed = RecvedMessage {
-                        msg_id: recv_msg.id,
-                        msg_vec: ink_prelude::vec![],
-                        processed: true,
-                    };
+            // the copy must be delivered to the destination the message is announced with
+            if AccountId::from(recv_msg.contract) != msg_instance.destination {
+                return Err(SubmitError::WrongDestination);
+            }
 
-                    self.msg_2_verify.insert(&key, &msg_processed);
+            // expired messages can only be finalized by `finalize_expired`
+            if self.is_expired(&msg_instance) {
+                return Err(SubmitError::Expired);
+            }
 
-                } else {
-                    self.msg_2_verify.insert(&key, &msg_instance);
-                }
+            let selection = self.msg_selections.get(&key).unwrap_or_default();
 
+            // only the selected routers can deliver the message, and only the inspectors during the inspection round
+            let selected = if msg_instance.held {
+                selection.inspectors.contains(&router_id)
             } else {
-                let msg_hash = recv_msg.into_hash::<ink_env::hash::Keccak256>();
+                selection.routers.contains(&router_id)
+            };
 
-                let mut msg_instance = RecvedMessage{
-                    msg_id: recv_msg.id,
-                    msg_vec: ink_prelude::vec![],
-                    processed: false,
-                };
+            if !selected {
+                return Err(SubmitError::NotSelected);
+            }
+
+            let msg_hash = recv_msg.into_hash::<ink::env::hash::Keccak256>();
+            let mut hash_found = false;
+
+            for ele in msg_instance.msg_vec.iter_mut() {
+                if ele.msg_hash == msg_hash {
+                    ele.submitters.push(router_id);
+                    hash_found = true;
+                    break;
+                }
+            }
 
+            if !hash_found {
                 let mut msg_info = MessageInfo {
-                    msg_hash: msg_hash,
+                    msg_hash,
                     msg_detail: recv_msg.into_bytes(),
-                    submitters: ink_prelude::vec![],
+                    submitters: ink::prelude::vec![],
                 };
                 msg_info.submitters.push(router_id);
                 msg_instance.msg_vec.push(msg_info);
+            }
+
+            let sqos = self.sqos_of(&msg_instance.destination);
+
+            if msg_instance.held {
+                // the copies submitted during the inspection round come from the inspectors
+                msg_instance.inspection_count = msg_instance.inspection_count.saturating_add(1);
+
+                if msg_instance.inspection_count >= selection.inspectors.len() as u16 {
+                    let vf_passed = self.simu_message_verification(&key, &msg_instance);
+
+                    Self::env().emit_event(MessageReleased {
+                        from_chain: key.0.clone(),
+                        msg_id: key.1,
+                        vf_passed,
+                    });
+
+                    self.msg_2_verify.insert(&key, &RecvedMessage::processed(recv_msg.id, msg_instance.destination, msg_instance.first_seen));
+                } else {
+                    self.msg_2_verify.insert(&key, &msg_instance);
+                }
+
+            // we comment off the following lines to manually call `simu_message_verification` for simulation
+            } else if msg_instance.get_submitter_count() >= sqos.copy_count {
+                // self.msg_2_verify.remove(&key);
+
+                // no randomness is needed if the inspection is disabled
+                let inspection_seed = if self.inspection_rate == 0 {
+                    Some(selection.seed)
+                } else {
+                    self.provider_seed(&key, ROUND_INSPECTION)
+                };
+
+                if let Some(seed) = inspection_seed {
+                    self.start_inspection(&key, msg_instance, selection, seed);
+                } else {
+                    // wait for the VRF output of the inspection, see `supply_vrf`.
+                    // Without the randomness of the runtime, the message fails at its timeout
+                    msg_instance.held = true;
+                    self.msg_2_verify.insert(&key, &msg_instance);
+
+                    Self::env().emit_event(MessageHeld {
+                        from_chain: key.0.clone(),
+                        msg_id: key.1,
+                        inspection_copy_count: 0,
+                    });
+                }
+
+            } else {
                 self.msg_2_verify.insert(&key, &msg_instance);
+            }
+
+            Ok(())
+        }
+
+        /// Finalize a pending message whose timeout has passed, anyone can call it to push stuck messages through
+        /// 
+        /// If the copies delivered before the timeout reach `min_partial_copies` of the SQoS settings, the message is verified with them,
+        /// otherwise it is marked as failed. A single copy must also be allowed by `allow_single_copy`.
+        /// A message held for inspection always fails, as not all of its inspectors have submitted their copies. An event `MessageExpired` will be emitted in both cases.
+        /// Returns whether the message passed the verification
+        #[ink(message)]
+        pub fn finalize_expired(&mut self, from_chain: ink::prelude::string::String, msg_id: u128) -> Result<bool, FinalizeError> {
+            let key = (from_chain, msg_id);
+
+            let msg_instance = self.msg_2_verify.get(&key).ok_or(FinalizeError::NotExist)?;
+            if msg_instance.processed {
+                return Err(FinalizeError::AlreadyProcessed);
+            }
+
+            if !self.is_expired(&msg_instance) {
+                return Err(FinalizeError::NotExpired);
+            }
+
+            let sqos = self.sqos_of(&msg_instance.destination);
+            let copies = msg_instance.get_submitter_count();
+            let mut msg_processed = RecvedMessage::processed(msg_id, msg_instance.destination, msg_instance.first_seen);
+
+            // a held message is released only by the copies of all its inspectors, the inspection cannot be skipped by waiting out the timeout
+            let enough = !msg_instance.held
+                && (copies >= sqos.min_partial_copies)
+                && ((copies > 1) || ((copies == 1) && sqos.allow_single_copy));
+            let vf_passed = if enough {
+                self.simu_message_verification(&key, &msg_instance)
+            } else {
+                msg_processed.failed = true;
 
-                self.msg_v_keys.push(key);
+                false
+            };
 
-                // at least two message copies 
+            if msg_instance.held {
+                Self::env().emit_event(MessageReleased {
+                    from_chain: key.0.clone(),
+                    msg_id: key.1,
+                    vf_passed,
+                });
             }
+
+            self.msg_2_verify.insert(&key, &msg_processed);
+
+            Self::env().emit_event(MessageExpired {
+                from_chain: key.0,
+                msg_id: key.1,
+                copies,
+                failed: msg_processed.failed,
+            });
+
+            Ok(vf_passed)
+        }
+
+        /// Whether the timeout of a pending message has passed
+        fn is_expired(&self, msg_instance: &RecvedMessage) -> bool {
+            let sqos = self.sqos_of(&msg_instance.destination);
+
+            Self::env().block_number() >= msg_instance.first_seen.saturating_add(sqos.timeout)
         }
 
-        /// Clear submitted messages manually
+        /// Clear at most `limit` submitted messages manually, the earliest first
+        /// Returns the number of the messages left
         #[ink(message)]
-        pub fn simu_clear_message(&mut self, flag: bool) {
-            for ele in self.msg_v_keys.iter() {
-                self.msg_2_verify.remove(ele);
+        pub fn simu_clear_message(&mut self, limit: u32) -> Result<u64, AdminError> {
+            self.ensure_role(Role::Admin)?;
+
+            for ele in self.msg_v_keys.pop_front(limit) {
+                self.msg_2_verify.remove(&ele);
             }
 
-            self.msg_v_keys.clear();
+            Ok(self.msg_v_keys.len())
         }
 
-        /// Get submitted messages
+        /// Get at most `limit` submitted messages starting from `cursor`
+        /// Returns the messages and the cursor of the next page, which is `None` if there are no more messages
         #[ink(message)]
-        pub fn simu_get_message(&self, flag: bool) -> ink_prelude::vec::Vec<RecvedMessage>{
-            let mut messages = ink_prelude::vec![];
-            for msg_key in self.msg_v_keys.iter() {
+        pub fn simu_get_message(&self, cursor: Option<u64>, limit: u32) -> (ink::prelude::vec::Vec<RecvedMessage>, Option<u64>) {
+            let (keys, next) = self.msg_v_keys.page(cursor, limit);
+            let mut messages = ink::prelude::vec![];
+            for msg_key in keys.iter() {
                 if let Some(msg) = self.msg_2_verify.get(msg_key) {
                     messages.push(msg);
                 }
             }
 
-            messages
+            (messages, next)
         }
 
         /// When enough message copies are submitted, `simu_message_verification` will be called internally
         /// The result will be cached in order to be checked manually
         /// and an event `VerifiedMessage` will be emitted to show the result, but the result event need to be decoded by `Polkadot.js` 
-        fn simu_message_verification(&mut self, msg_instance: &RecvedMessage) {
-            if msg_instance.msg_vec.len() > 1 {
-                let mut index_cred = ink_prelude::vec![];
-                let mut idx: u16 = 0;
-                let mut total_cred = 0;
+        /// 
+        /// Returns whether the verification passed
+        fn simu_message_verification(&mut self, key: &(ink::prelude::string::String, u128), msg_instance: &RecvedMessage) -> bool {
+            let sqos = self.sqos_of(&msg_instance.destination);
+
+            let mut all_submitters = ink::prelude::vec![];
+            for msg_ele in msg_instance.msg_vec.iter() {
+                all_submitters.extend_from_slice(&msg_ele.submitters);
+            }
+
+            if (all_submitters.len() == 1) && !sqos.allow_single_copy {
+                let verified_msg = VerifiedMessage {
+                    vf_passed: false,
+                    submitted: ink::prelude::vec![],
+                };
+
+                Self::env().emit_event(verified_msg);
+
+                false
+            } else if msg_instance.msg_vec.len() > 1 {
+                let mut index_cred = ink::prelude::vec![];
+                let mut total_cred: u128 = 0;
 
                 let mut verified_msg = VerifiedMessage {
                     vf_passed: false,
-                    submitted: ink_prelude::vec![],
+                    submitted: ink::prelude::vec![],
                 };
 
                 // just for showing the result of the verification
-                self.cache_verified_keys.push(msg_instance.msg_id);
+                self.cache_verified_keys.insert(&msg_instance.msg_id);
                 let mut cache_verified = VerifiedCache {
                     msg_id: msg_instance.msg_id,
-                    submitted: ink_prelude::vec![],
+                    submitted: ink::prelude::vec![],
                     vf_passed: false,
                 };
 
-                for msg_ele in msg_instance.msg_vec.iter() {
+                for (idx, msg_ele) in (0_u16..).zip(msg_instance.msg_vec.iter()) {
                     let mut vf_info = VerifyInfo {
                         cred_sum: 0,
-                        submitters: ink_prelude::vec![],
+                        submitters: ink::prelude::vec![],
                     };
 
-                    let mut sum_cred = 0;
+                    let mut sum_cred: u128 = 0;
                     for submitter in msg_ele.submitters.iter() {
-                        if let Some(router) = self.sim_routers.get(&submitter) {
-                            sum_cred += router.1;
+                        if let Some(router) = self.routers.get(submitter) {
+                            sum_cred = sum_cred.saturating_add(router.credibility as u128);
 
-                            vf_info.submitters.push(router.0);
+                            vf_info.submitters.push(*submitter);
                         }
                     }
 
-                    vf_info.cred_sum = sum_cred as u128;
+                    vf_info.cred_sum = sum_cred;
                     verified_msg.submitted.push(vf_info.clone());
                     // just for showing the result of the verification
                     cache_verified.submitted.push(vf_info);
 
-                    index_cred.push((idx, sum_cred as u128));
-                    idx += 1;
-                    total_cred += sum_cred as u128;
+                    index_cred.push((idx, sum_cred));
+                    total_cred = total_cred.saturating_add(sum_cred);
                 }
 
                 let coe: u128 = 10000;
@@ -534,18 +1709,27 @@ mod algorithm {
                 let mut max_cred: (u16, u128) = (0, 0);
 
                 for cred_ele in index_cred.iter_mut() {
-                    cred_ele.1 = cred_ele.1 * coe / total_cred;
+                    // `0` if no submitter has credibility or they have all been removed
+                    cred_ele.1 = cred_ele.1.saturating_mul(coe).checked_div(total_cred).unwrap_or(0);
                     if max_cred.1 < cred_ele.1 {
                         max_cred = (cred_ele.0, cred_ele.1);
                     }
                 }
 
-                if max_cred.1 >= self.vf_threshold {
+                // no copy can be accepted without any credibility, even if the threshold is `0`
+                if (total_cred > 0) && (max_cred.1 >= sqos.vf_threshold) {
                     verified_msg.vf_passed = true;
                     // just for showing the result of the verification
                     cache_verified.vf_passed = true;
 
+                    self.accepted_msgs.insert(key, &AcceptedMessage {
+                        msg_hash: msg_instance.msg_vec[max_cred.0 as usize].msg_hash,
+                        submitters: msg_instance.msg_vec[max_cred.0 as usize].submitters.clone(),
+                    });
+
                     Self::env().emit_event(verified_msg);
+
+                    self.evaluate_submitters(key, msg_instance, max_cred.0 as usize);
                 } else {
                     verified_msg.vf_passed = false;
                     // just for showing the result of the verification
@@ -555,8 +1739,9 @@ mod algorithm {
                 }
 
                 // just for showing the result of the verification
-                self.cache_verified.insert(&msg_instance.msg_id, &cache_verified);
+                self.cache_verified.insert(msg_instance.msg_id, &cache_verified);
 
+                cache_verified.vf_passed
             } else if msg_instance.msg_vec.len() == 1{
                 let vf_info = VerifyInfo {
                     cred_sum: 100,
@@ -565,47 +1750,171 @@ mod algorithm {
                 
                 let verified_msg = VerifiedMessage {
                     vf_passed: true,
-                    submitted: ink_prelude::vec![vf_info.clone()],
+                    submitted: ink::prelude::vec![vf_info.clone()],
                 };
                 
                 // just for showing the result of the verification
-                self.cache_verified_keys.push(msg_instance.msg_id);
-                self.cache_verified.insert(&msg_instance.msg_id, &VerifiedCache {
+                self.cache_verified_keys.insert(&msg_instance.msg_id);
+                self.cache_verified.insert(msg_instance.msg_id, &VerifiedCache {
                     msg_id: msg_instance.msg_id,
-                    submitted: ink_prelude::vec![vf_info],
+                    submitted: ink::prelude::vec![vf_info],
                     vf_passed: true,
                 });
 
+                self.accepted_msgs.insert(key, &AcceptedMessage {
+                    msg_hash: msg_instance.msg_vec[0].msg_hash,
+                    submitters: msg_instance.msg_vec[0].submitters.clone(),
+                });
+
                 Self::env().emit_event(verified_msg);
+
+                self.evaluate_submitters(key, msg_instance, 0);
+
+                true
             } else {
                 let verified_msg = VerifiedMessage {
                     vf_passed: false,
-                    submitted: ink_prelude::vec![],
+                    submitted: ink::prelude::vec![],
                 };
 
                 Self::env().emit_event(verified_msg);
+
+                false
+            }
+        }
+
+        /// Router evaluation driven by the verification result
+        /// The routers who submitted the accepted copy `msg_instance.msg_vec[accepted]` behaved honestly,
+        /// and the ones who submitted the other copies behaved evilly.
+        /// The deliveries of the honest routers are recorded in `Tokenomics` for the epoch rewards.
+        /// All the adjustments are emitted in one event `CredibilityAdjusted`
+        fn evaluate_submitters(&mut self, key: &(ink::prelude::string::String, u128), msg_instance: &RecvedMessage, accepted: usize) {
+            let mut adjustments = ink::prelude::vec![];
+
+            for (idx, msg_ele) in msg_instance.msg_vec.iter().enumerate() {
+                let honest = idx == accepted;
+
+                for submitter in msg_ele.submitters.iter() {
+                    if let Some(mut router) = self.routers.get(submitter) {
+                        let cred_before = router.credibility;
+                        if honest {
+                            self.evaluate_honest(&mut router);
+                        } else {
+                            self.evaluate_evil(&mut router);
+                        }
+                        self.save_router(submitter, &router);
+
+                        if honest {
+                            self.record_delivery(submitter, router.credibility);
+                        }
+
+                        adjustments.push(CredibilityAdjustment {
+                            router: *submitter,
+                            honest,
+                            cred_before,
+                            cred_after: router.credibility,
+                        });
+                    }
+                }
+            }
+
+            Self::env().emit_event(CredibilityAdjusted {
+                from_chain: key.0.clone(),
+                msg_id: key.1,
+                model: self.eval_params.model,
+                adjustments,
+            });
+        }
+
+        /// Verifiable Passive Inspection
+        /// Randomly decides whether a delivered message is held for an inspection round, 
+        /// with the probability `inspection_rate / 10000`
+        fn draw_inspection(&self, seed: &[u8; 32]) -> bool {
+            if self.inspection_rate == 0 {
+                return false;
+            }
+
+            let rand_num = selection::uniform_below(&mut SeededRandom::new(seed, DOMAIN_INSPECTION_DRAW), 10000);
+
+            rand_num < self.inspection_rate
+        }
+
+        /// Draw the inspection of a delivered message with `seed`.
+        /// The message is held for an inspection round if inspectors are selected, otherwise it is verified
+        fn start_inspection(&mut self, key: &(ink::prelude::string::String, u128), mut msg_instance: RecvedMessage, selection: MessageSelection, seed: [u8; 32]) {
+            // the inspectors are the extra routers besides the ones selected to deliver the message
+            let inspectors = if self.draw_inspection(&seed) {
+                self.select_routers(self.inspection_copy_count, &selection.routers, &seed, DOMAIN_INSPECTORS)
+            } else {
+                ink::prelude::vec![]
+            };
+
+            if !inspectors.is_empty() {
+                // hold the message until the inspectors finish the inspection round
+                msg_instance.held = true;
+                self.msg_2_verify.insert(key, &msg_instance);
+
+                self.msg_selections.insert(key, &MessageSelection {
+                    inspectors: inspectors.clone(),
+                    inspection_seed: Some(seed),
+                    ..selection
+                });
+
+                Self::env().emit_event(MessageHeld {
+                    from_chain: key.0.clone(),
+                    msg_id: key.1,
+                    inspection_copy_count: inspectors.len() as u16,
+                });
+
+                Self::env().emit_event(RoutersSelected {
+                    from_chain: key.0.clone(),
+                    msg_id: key.1,
+                    routers: inspectors,
+                    inspection: true,
+                    source: self.randomness,
+                    seed,
+                });
+            } else {
+                let vf_passed = self.simu_message_verification(key, &msg_instance);
+
+                // the message was waiting for the randomness of the inspection
+                if msg_instance.held {
+                    Self::env().emit_event(MessageReleased {
+                        from_chain: key.0.clone(),
+                        msg_id: key.1,
+                        vf_passed,
+                    });
+                }
+
+                self.msg_2_verify.insert(key, &RecvedMessage::processed(key.1, msg_instance.destination, msg_instance.first_seen));
             }
         }
 
+        /// Get at most `limit` cached verification results starting from `cursor`
+        /// Returns the results and the cursor of the next page, which is `None` if there are no more results
         #[ink(message)]
-        pub fn get_verified_results(&self, flag: bool) -> ink_prelude::vec::Vec<VerifiedCache> {
-            let mut rst = ink_prelude::vec![];
-            for ele in self.cache_verified_keys.iter() {
+        pub fn get_verified_results(&self, cursor: Option<u64>, limit: u32) -> (ink::prelude::vec::Vec<VerifiedCache>, Option<u64>) {
+            let (keys, next) = self.cache_verified_keys.page(cursor, limit);
+            let mut rst = ink::prelude::vec![];
+            for ele in keys.iter() {
                 if let Some(verified) = self.cache_verified.get(ele) {
                     rst.push(verified);
                 }
             }
 
-            rst
+            (rst, next)
         }
 
+        /// Clear at most `limit` cached verification results, returns the number of the results left
         #[ink(message)]
-        pub fn clear_verified_cache(&mut self) {
-            for ele in self.cache_verified_keys.iter() {
+        pub fn clear_verified_cache(&mut self, limit: u32) -> Result<u64, AdminError> {
+            self.ensure_role(Role::Admin)?;
+
+            for ele in self.cache_verified_keys.pop_front(limit) {
                 self.cache_verified.remove(ele);
             }
 
-            self.cache_verified_keys.clear();
+            Ok(self.cache_verified_keys.len())
         }
 
         /// simulation of node evaluation
@@ -613,114 +1922,251 @@ mod algorithm {
         /// This is a on-chain prototype for routers eveluation to show the principle of node evaluation algorithms
         /// When a router does `do_honest_once`, its credibility will increase
         /// On the contrary, when a router does `do_evil_once`, its credibility will decrease
+        /// 
+        /// Only operators can evaluate routers manually
         #[ink(message)]
-        pub fn do_honest_once(&mut self, id: u16) {
-            if let Some(mut router) = self.sim_routers.get(id) {
-                // TODO: increase credibility
-                if router.1 < self.coe_middle_cred {
-                    router.1 = 10
-                        * (router.1 - self.coe_min_cred)
-                        / self.coe_range_cred
-                        + router.1;
-                } else {
-                    router.1 = 10
-                        * (self.coe_max_cred - router.1)
-                        / self.coe_range_cred
-                        + router.1;
-                }
+        pub fn do_honest_once(&mut self, router_id: AccountId) -> Result<(), AdminError> {
+            self.ensure_role(Role::Operator)?;
 
-                self.sim_routers.insert(&id, &router);
-            }
+            let mut router = self.routers.get(router_id).ok_or(AdminError::RouterNotExist)?;
+            self.evaluate_honest(&mut router);
+
+            self.save_router(&router_id, &router);
+            Ok(())
         }
 
         #[ink(message)]
-        pub fn do_evil_once(&mut self, id: u16) {
-            if let Some(mut router) = self.sim_routers.get(id) {
-                // TODO: decrease credibility
+        pub fn do_evil_once(&mut self, router_id: AccountId) -> Result<(), AdminError> {
+            self.ensure_role(Role::Operator)?;
+
+            let mut router = self.routers.get(router_id).ok_or(AdminError::RouterNotExist)?;
+            self.evaluate_evil(&mut router);
 
-                router.1 = router.1
-                    - 20
-                        * (router.1 - self.coe_min_cred)
-                        / self.coe_range_cred;
+            self.save_router(&router_id, &router);
+            Ok(())
+        }
 
-                self.sim_routers.insert(&id, &router);
+        fn cred_range(&self) -> CredibilityRange {
+            CredibilityRange {
+                min: self.coe_min_cred,
+                middle: self.coe_middle_cred,
+                max: self.coe_max_cred,
             }
         }
 
+        /// Increase the credibility of `router` for one honest behavior with the evaluation model in use
+        fn evaluate_honest(&self, router: &mut RouterInfo) {
+            let blocks_since_offence = router.last_offence.map(|block| Self::env().block_number().saturating_sub(block));
+            router.credibility = self.eval_params.honest(&self.cred_range(), router.credibility, blocks_since_offence);
+        }
+
+        /// Decrease the credibility of `router` for one evil behavior with the evaluation model in use
+        fn evaluate_evil(&self, router: &mut RouterInfo) {
+            router.credibility = self.eval_params.evil(&self.cred_range(), router.credibility, router.offences);
+            router.offences = router.offences.saturating_add(1);
+            router.last_offence = Some(Self::env().block_number());
+        }
+
+        /// Select the evaluation model and set its parameters
         #[ink(message)]
-        pub fn get_credibility(&self, id: u16) -> Option<u32> {
-            if let Some(router) = self.sim_routers.get(id) {
-                Some(router.1)
-            } else {
-                None
+        pub fn set_evaluation_params(&mut self, params: EvaluationParams) -> Result<(), AdminError> {
+            self.ensure_role(Role::Admin)?;
+
+            if !params.is_valid(&self.cred_range()) {
+                return Err(AdminError::ParamInvalid);
             }
+
+            self.eval_params = params;
+            Ok(())
         }
 
         #[ink(message)]
-        pub fn do_honest(&mut self, id: u16, times: u32) {
-            let mut honest_rst = EvaluateResult {
-                behavior_type: ink_prelude::string::String::from("honest"),
-                results: ink_prelude::vec![],
-            };
+        pub fn get_evaluation_params(&self) -> EvaluationParams {
+            self.eval_params.clone()
+        }
 
-            if let Some(mut router) = self.sim_routers.get(id) {
-                honest_rst.results.push(router.1);
-
-                // increase credibility
-                let mut count = 0;
-                while count < times {
-                    
-                    if router.1 < self.coe_middle_cred {
-                        router.1 = 10
-                            * (router.1 - self.coe_min_cred)
-                            / self.coe_range_cred
-                            + router.1;
-                    } else {
-                        router.1 = 10
-                            * (self.coe_max_cred - router.1)
-                            / self.coe_range_cred
-                            + router.1;
-                    }
+        /// Set the range of credibility, a router is registered with the `middle` credibility.
+        /// The `floor` and `ceiling` of the evaluation params in use must be in the new range
+        #[ink(message)]
+        pub fn set_credibility_range(&mut self, min: u32, middle: u32, max: u32) -> Result<(), AdminError> {
+            self.ensure_role(Role::Admin)?;
 
-                    honest_rst.results.push(router.1);
+            let range = CredibilityRange { min, middle, max };
+            if (min > middle) || (middle > max) || !self.eval_params.is_valid(&range) {
+                return Err(AdminError::ParamInvalid);
+            }
 
-                    count += 1;
-                }
+            self.coe_min_cred = min;
+            self.coe_middle_cred = middle;
+            self.coe_max_cred = max;
+            self.coe_range_cred = range.range();
+            Ok(())
+        }
 
-                self.sim_routers.insert(&id, &router);
+        /// Get the range of credibility as `(min, middle, max)`
+        #[ink(message)]
+        pub fn get_credibility_range(&self) -> (u32, u32, u32) {
+            (self.coe_min_cred, self.coe_middle_cred, self.coe_max_cred)
+        }
 
-                Self::env().emit_event(honest_rst);
+        #[ink(message)]
+        pub fn get_credibility(&self, router: AccountId) -> Option<u32> {
+            if let Some(router) = self.routers.get(router) {
+                Some(router.credibility)
+            } else {
+                None
             }
         }
 
         #[ink(message)]
-        pub fn do_evil(&mut self, id: u16, times: u32) {
+        pub fn do_honest(&mut self, router_id: AccountId, times: u32) -> Result<(), AdminError> {
+            self.ensure_role(Role::Operator)?;
+
+            let mut honest_rst = EvaluateResult {
+                behavior_type: ink::prelude::string::String::from("honest"),
+                model: self.eval_params.model,
+                results: ink::prelude::vec![],
+            };
+
+            let mut router = self.routers.get(router_id).ok_or(AdminError::RouterNotExist)?;
+            honest_rst.results.push(router.credibility);
+
+            // increase credibility
+            let mut count = 0;
+            while count < times {
+                self.evaluate_honest(&mut router);
+
+                honest_rst.results.push(router.credibility);
+
+                count += 1;
+            }
+
+            self.save_router(&router_id, &router);
+
+            Self::env().emit_event(honest_rst);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn do_evil(&mut self, router_id: AccountId, times: u32) -> Result<(), AdminError> {
+            self.ensure_role(Role::Operator)?;
+
             let mut evil_rst = EvaluateResult {
-                behavior_type: ink_prelude::string::String::from("evil"),
-                results: ink_prelude::vec![],
+                behavior_type: ink::prelude::string::String::from("evil"),
+                model: self.eval_params.model,
+                results: ink::prelude::vec![],
             };
 
-            if let Some(mut router) = self.sim_routers.get(id) {
-                evil_rst.results.push(router.1);
-                
-                // decrease credibility
-                let mut count = 0;
-                while count < times {
-                    
-                    router.1 = router.1
-                                - 20
-                                * (router.1 - self.coe_min_cred)
-                                / self.coe_range_cred;
-
-                    evil_rst.results.push(router.1);
-
-                    count += 1;
-                }
+            let mut router = self.routers.get(router_id).ok_or(AdminError::RouterNotExist)?;
+            evil_rst.results.push(router.credibility);
+            
+            // decrease credibility
+            let mut count = 0;
+            while count < times {
+                self.evaluate_evil(&mut router);
 
-                self.sim_routers.insert(&id, &router);
+                evil_rst.results.push(router.credibility);
 
-                Self::env().emit_event(evil_rst);
+                count += 1;
             }
+
+            self.save_router(&router_id, &router);
+
+            Self::env().emit_event(evil_rst);
+            Ok(())
+        }
+
+        /// Set the status of a router, e.g. to release a jailed router
+        #[ink(message)]
+        pub fn set_router_status(&mut self, router_id: AccountId, status: RouterStatus) -> Result<(), AdminError> {
+            self.ensure_role(Role::Operator)?;
+
+            let mut router = self.routers.get(router_id).ok_or(AdminError::RouterNotExist)?;
+            router.status = status;
+            self.save_router(&router_id, &router);
+
+            Self::env().emit_event(RouterUpdated {
+                router: router_id,
+                info: router,
+            });
+
+            Ok(())
+        }
+
+        /// Verifiable Fraud Proof
+        /// 
+        /// Anyone can challenge a verified message by proving that a router who submitted the accepted copy of it 
+        /// has signed a copy whose hash differs from the accepted one.
+        /// The routers who submitted the other copies cannot be proven, as they have been evaluated as evil for them already.
+        /// 
+        /// `signature` is the secp256k1 signature of the router over the keccak256 hash of `recv_msg`,
+        /// the router is authenticated as its `AccountId` is the blake2x256 hash of the recovered compressed public key.
+        /// So only the routers with ECDSA accounts can be proven, the routers with sr25519 or ed25519 accounts cannot.
+        /// When the proof is valid, the credibility of the router decreases as `do_evil_once` does, the router is jailed,
+        /// `slash_fraction` of its stake is slashed in `Tokenomics` with the challenger as the reporter,
+        /// and the slash is recorded and emitted as `RouterSlashed`
+        #[ink(message)]
+        pub fn submit_fraud_proof(&mut self, recv_msg: super::IReceivedMessage, router_id: AccountId, signature: [u8;65]) -> Result<(), FraudProofError> {
+            let key = (recv_msg.from_chain.clone(), recv_msg.id);
+
+            let accepted = self.accepted_msgs.get(&key).ok_or(FraudProofError::NotVerified)?;
+            let mut router = self.routers.get(router_id).ok_or(FraudProofError::UnknownRouter)?;
+
+            if !accepted.submitters.contains(&router_id) {
+                return Err(FraudProofError::NotSubmitter);
+            }
+
+            if self.slash_records.contains(&(key.clone(), router_id)) {
+                return Err(FraudProofError::AlreadySlashed);
+            }
+
+            let fraud_hash = recv_msg.into_hash::<ink::env::hash::Keccak256>();
+            if fraud_hash == accepted.msg_hash {
+                return Err(FraudProofError::SameHash);
+            }
+
+            let mut recovered = [0; 33];
+            if ink::env::ecdsa_recover(&signature, &fraud_hash, &mut recovered).is_err() {
+                return Err(FraudProofError::InvalidSignature);
+            }
+
+            let mut signer = <ink::env::hash::Blake2x256 as ink::env::hash::HashOutput>::Type::default();
+            ink::env::hash_encoded::<ink::env::hash::Blake2x256, _>(&recovered, &mut signer);
+            if AccountId::from(signer) != router_id {
+                return Err(FraudProofError::InvalidSignature);
+            }
+
+            let cred_before = router.credibility;
+            self.evaluate_evil(&mut router);
+            router.status = RouterStatus::Jailed;
+            self.save_router(&router_id, &router);
+
+            let challenger = Self::env().caller();
+            let record = SlashRecord {
+                challenger,
+                accepted_hash: accepted.msg_hash,
+                fraud_hash,
+                cred_before,
+                cred_after: router.credibility,
+                block: Self::env().block_number(),
+                stake_slashed: self.slash_stake(&router_id, &challenger),
+            };
+            self.slash_records.insert(&(key.clone(), router_id), &record);
+
+            Self::env().emit_event(RouterSlashed {
+                from_chain: key.0,
+                msg_id: key.1,
+                router: router_id,
+                record,
+            });
+
+            Ok(())
+        }
+
+        /// Get the slash record of `router` on the message
+        #[ink(message)]
+        pub fn get_slash_record(&self, from_chain: ink::prelude::string::String, msg_id: u128, router: AccountId) -> Option<SlashRecord> {
+            self.slash_records.get(&((from_chain, msg_id), router))
         }
     }
 
@@ -732,8 +2178,1240 @@ mod algorithm {
         /// Imports all the definitions from the outer scope so we can use them here.
         use super::*;
 
-        /// Imports `ink_lang` so we can use `#[ink::test]`.
-        use ink_lang as ink;
+        use payload::message_define::{IReceivedMessage, ISession};
+
+        /// The destination contract of the test messages
+        const DESTINATION: [u8; 32] = [0x42; 32];
+
+        /// Mocks the `FetchRandom` chain extension with a seed folded from the subject,
+        /// such that the selections in the tests are deterministic.
+        /// The seed cannot be hashed by `ink::env` as the engine is borrowed during the call
+        struct MockedRandom;
+
+        impl ink::env::test::ChainExtension for MockedRandom {
+            fn func_id(&self) -> u32 {
+                1101
+            }
+
+            fn call(&mut self, input: &[u8], output: &mut ink::prelude::vec::Vec<u8>) -> u32 {
+                let mut seed = [0u8; 32];
+                for (idx, byte) in input.iter().enumerate() {
+                    seed[idx % 32] = seed[idx % 32].wrapping_mul(31).wrapping_add(*byte);
+                }
+
+                scale::Encode::encode_to(&seed, output);
+                0
+            }
+        }
+
+        /// Mocks the `FetchRandom` chain extension failing to provide the randomness
+        struct UnavailableRandom;
+
+        impl ink::env::test::ChainExtension for UnavailableRandom {
+            fn func_id(&self) -> u32 {
+                1101
+            }
+
+            fn call(&mut self, _input: &[u8], _output: &mut ink::prelude::vec::Vec<u8>) -> u32 {
+                1
+            }
+        }
+
+        fn accounts() -> ink::env::test::DefaultAccounts<Environment> {
+            ink::env::test::default_accounts::<Environment>()
+        }
+
+        fn set_caller(caller: AccountId) {
+            ink::env::test::set_caller::<Environment>(caller);
+        }
+
+        /// Deploy the contract by `alice`, and register `routers`
+        fn setup(routers: &[AccountId]) -> AlgorithmPrototype {
+            ink::env::test::register_chain_extension(MockedRandom);
+
+            set_caller(accounts().alice);
+            let mut algorithm = AlgorithmPrototype::new(true);
+
+            for router in routers.iter() {
+                set_caller(*router);
+                assert!(algorithm.register_router(ink::prelude::string::String::from("router")));
+            }
+
+            set_caller(accounts().alice);
+            algorithm
+        }
+
+        /// The five routers other than the owner `alice`
+        fn five_routers() -> ink::prelude::vec::Vec<AccountId> {
+            let accounts = accounts();
+            ink::prelude::vec![accounts.bob, accounts.charlie, accounts.django, accounts.eve, accounts.frank]
+        }
+
+        /// A copy of the message `id`, copies with different `data` have different hashes
+        fn message(id: u128, data: u8) -> IReceivedMessage {
+            IReceivedMessage {
+                id,
+                from_chain: ink::prelude::string::String::from("ETHEREUM"),
+                to_chain: ink::prelude::string::String::from("POLKADOT"),
+                sender: ink::prelude::vec![1; 32],
+                signer: ink::prelude::vec![2; 32],
+                sqos: ink::prelude::vec![],
+                contract: DESTINATION,
+                action: [0x3a, 0x6e, 0x96, 0x96],
+                data: ink::prelude::vec![data],
+                session: ISession {
+                    id: 0,
+                    callback: ink::prelude::vec![],
+                },
+            }
+        }
+
+        /// Announce the message `msg_id` to `DESTINATION` by the owner `alice`, returns the selected routers
+        fn announce(algorithm: &mut AlgorithmPrototype, msg_id: u128) -> ink::prelude::vec::Vec<AccountId> {
+            set_caller(accounts().alice);
+            algorithm.announce_message(ink::prelude::string::String::from("ETHEREUM"), msg_id, AccountId::from(DESTINATION)).unwrap()
+        }
+
+        /// Submit `recv_msg` by `router`, a rejected copy is returned as an error
+        fn submit(algorithm: &mut AlgorithmPrototype, router: AccountId, recv_msg: IReceivedMessage) -> Result<(), SubmitError> {
+            set_caller(router);
+            match algorithm.simu_submit_message(recv_msg)? {
+                SubmitOutcome::Accepted => Ok(()),
+                SubmitOutcome::Rejected(error) => Err(error),
+            }
+        }
+
+        fn credibility(algorithm: &AlgorithmPrototype, router: AccountId) -> u32 {
+            algorithm.get_credibility(router).unwrap()
+        }
+
+        /// The cached verification result of the message `msg_id`
+        fn verified(algorithm: &AlgorithmPrototype, msg_id: u128) -> Option<bool> {
+            let (results, _) = algorithm.get_verified_results(None, 100);
+            results.iter().find(|ele| ele.msg_id == msg_id).map(|ele| ele.vf_passed)
+        }
+
+        fn is_processed(algorithm: &AlgorithmPrototype, msg_id: u128) -> bool {
+            let (messages, _) = algorithm.simu_get_message(None, 100);
+            messages.iter().any(|ele| (ele.msg_id == msg_id) && ele.processed)
+        }
+
+        type Event = <AlgorithmPrototype as ink::reflect::ContractEventBase>::Type;
+
+        /// The events emitted after the first `from` recorded events
+        fn events_since(from: usize) -> ink::prelude::vec::Vec<Event> {
+            ink::env::test::recorded_events()
+                .skip(from)
+                .map(|event| <Event as scale::Decode>::decode(&mut &event.data[..]).unwrap())
+                .collect()
+        }
+
+        #[ink::test]
+        fn selection_is_deterministic_with_mocked_randomness() {
+            let algorithm = setup(&five_routers());
+
+            let selected = algorithm.selection_test(3).unwrap();
+            assert_eq!(selected.len(), 3);
+            assert_eq!(algorithm.selection_test(3), Some(selected.clone()));
+
+            for router in selected.iter() {
+                assert!(five_routers().contains(router));
+                assert_eq!(selected.iter().filter(|ele| *ele == router).count(), 1);
+            }
+        }
+
+        #[ink::test]
+        fn selection_with_fewer_routers_than_n() {
+            let accounts = accounts();
+            let algorithm = setup(&[accounts.bob, accounts.charlie, accounts.django]);
+
+            assert_eq!(algorithm.selection_test(3).map(|selected| selected.len()), Some(3));
+            assert_eq!(algorithm.selection_test(4), None);
+            assert_eq!(algorithm.selection_test(5), None);
+        }
+
+        #[ink::test]
+        fn unavailable_randomness_selects_no_routers() {
+            let mut algorithm = setup(&five_routers());
+            ink::env::test::register_chain_extension(UnavailableRandom);
+
+            assert_eq!(algorithm.selection_test(1), None);
+            assert!(algorithm.selection_statistic(10).is_none());
+
+            set_caller(accounts().alice);
+            assert_eq!(algorithm.announce_message(ink::prelude::string::String::from("ETHEREUM"), 1, AccountId::from(DESTINATION)), Err(SubmitError::RandomnessNotReady));
+            assert!(algorithm.get_selection(ink::prelude::string::String::from("ETHEREUM"), 1).is_none());
+        }
+
+        #[ink::test]
+        fn duplicate_submission_is_rejected() {
+            let mut algorithm = setup(&five_routers());
+            let bob = accounts().bob;
+
+            announce(&mut algorithm, 1);
+            assert_eq!(submit(&mut algorithm, bob, message(1, 0)), Ok(()));
+            assert_eq!(submit(&mut algorithm, bob, message(1, 0)), Err(SubmitError::DuplicateSubmission));
+            // a different copy of the same message is a duplicate as well
+            assert_eq!(submit(&mut algorithm, bob, message(1, 1)), Err(SubmitError::DuplicateSubmission));
+
+            let (messages, _) = algorithm.simu_get_message(None, 100);
+            assert_eq!(messages.len(), 1);
+            assert_eq!(messages[0].get_submitter_count(), 1);
+        }
+
+        #[ink::test]
+        fn unregistered_and_unselected_routers_are_rejected() {
+            let accounts = accounts();
+            let mut all_routers = five_routers();
+            all_routers.push(accounts.alice);
+            let mut algorithm = setup(&all_routers);
+
+            assert_eq!(submit(&mut algorithm, AccountId::from([0xff; 32]), message(1, 0)), Err(SubmitError::UnknownRouter));
+
+            // five of the six routers are selected
+            set_caller(accounts.alice);
+            let selected = algorithm.announce_message(ink::prelude::string::String::from("ETHEREUM"), 1, AccountId::from(DESTINATION)).unwrap();
+            assert_eq!(selected.len(), 5);
+            let unselected = all_routers.iter().find(|ele| !selected.contains(ele)).unwrap();
+
+            assert_eq!(submit(&mut algorithm, *unselected, message(1, 0)), Err(SubmitError::NotSelected));
+        }
+
+        #[ink::test]
+        fn rejected_copies_keep_the_event_and_change_nothing() {
+            let accounts = accounts();
+            let mut all_routers = five_routers();
+            all_routers.push(accounts.alice);
+            let mut algorithm = setup(&all_routers);
+
+            // only `MessageRejected` is emitted for a message not announced, which is not announced by the copy
+            let events = ink::env::test::recorded_events().count();
+            set_caller(accounts.bob);
+            assert_eq!(algorithm.simu_submit_message(message(1, 0)), Ok(SubmitOutcome::Rejected(SubmitError::NotAnnounced)));
+            assert_eq!(ink::env::test::recorded_events().count(), events + 1);
+            assert!(algorithm.get_selection(ink::prelude::string::String::from("ETHEREUM"), 1).is_none());
+            assert!(algorithm.simu_get_message(None, 100).0.is_empty());
+
+            // a router which is not selected
+            let selected = announce(&mut algorithm, 1);
+            let unselected = *all_routers.iter().find(|ele| !selected.contains(ele)).unwrap();
+            let events = ink::env::test::recorded_events().count();
+            set_caller(unselected);
+            assert_eq!(algorithm.simu_submit_message(message(1, 0)), Ok(SubmitOutcome::Rejected(SubmitError::NotSelected)));
+            assert_eq!(ink::env::test::recorded_events().count(), events + 1);
+            assert!(algorithm.simu_get_message(None, 100).0[0].msg_vec.is_empty());
+
+            // the callers which are not working routers fail the call
+            set_caller(AccountId::from([0xff; 32]));
+            assert_eq!(algorithm.simu_submit_message(message(1, 0)), Err(SubmitError::UnknownRouter));
+            assert_eq!(ink::env::test::recorded_events().count(), events + 1);
+
+            set_caller(selected[0]);
+            assert_eq!(algorithm.simu_submit_message(message(1, 0)), Ok(SubmitOutcome::Accepted));
+            assert_eq!(algorithm.simu_get_message(None, 100).0[0].msg_vec.len(), 1);
+        }
+
+        #[ink::test]
+        fn copies_must_match_the_announced_destination() {
+            let accounts = accounts();
+            let routers = five_routers();
+            let mut algorithm = setup(&routers);
+
+            // a router cannot announce the message with another destination
+            set_caller(accounts.bob);
+            assert_eq!(algorithm.announce_message(ink::prelude::string::String::from("ETHEREUM"), 1, AccountId::from([0x43; 32])), Err(SubmitError::Unauthorized));
+
+            set_caller(accounts.alice);
+            assert_eq!(algorithm.grant_role(accounts.bob, Role::Announcer), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(algorithm.announce_message(ink::prelude::string::String::from("ETHEREUM"), 1, AccountId::from(DESTINATION)).map(|selected| selected.len()), Ok(5));
+
+            let mut wrong = message(1, 0);
+            wrong.contract = [0x43; 32];
+            assert_eq!(submit(&mut algorithm, routers[0], wrong), Err(SubmitError::WrongDestination));
+            assert_eq!(submit(&mut algorithm, routers[0], message(1, 0)), Ok(()));
+
+        }
+
+        #[ink::test]
+        fn copies_cannot_announce_the_message() {
+            let routers = five_routers();
+            let mut algorithm = setup(&routers);
+
+            // another destination accepts a single copy
+            set_caller(AccountId::from([0x43; 32]));
+            assert!(algorithm.set_sqos(SQoSProfile {
+                copy_count: 1,
+                vf_threshold: 0,
+                timeout: 100,
+                allow_single_copy: true,
+                min_partial_copies: 1,
+            }));
+
+            // a router cannot pick the destination of a message not announced by its copy
+            let mut weak = message(1, 1);
+            weak.contract = [0x43; 32];
+            for router in routers.iter() {
+                assert_eq!(submit(&mut algorithm, *router, weak.clone()), Err(SubmitError::NotAnnounced));
+            }
+            assert_eq!(verified(&algorithm, 1), None);
+            assert!(algorithm.simu_get_message(None, 100).0.is_empty());
+
+            // the honest copies are delivered after the message is announced
+            assert_eq!(announce(&mut algorithm, 1).len(), 5);
+            for router in routers.iter() {
+                assert_eq!(submit(&mut algorithm, *router, message(1, 0)), Ok(()));
+            }
+            assert_eq!(verified(&algorithm, 1), Some(true));
+        }
+
+        #[ink::test]
+        fn exactly_msg_copy_count_copies_verify_the_message() {
+            let routers = five_routers();
+            let mut algorithm = setup(&routers);
+            let (_, msg_copy_count, _) = algorithm.get_sysinfo();
+            assert_eq!(msg_copy_count as usize, routers.len());
+
+            announce(&mut algorithm, 1);
+
+            for router in routers[..routers.len() - 1].iter() {
+                assert_eq!(submit(&mut algorithm, *router, message(1, 0)), Ok(()));
+            }
+
+            // one copy short
+            assert_eq!(verified(&algorithm, 1), None);
+            assert!(!is_processed(&algorithm, 1));
+
+            assert_eq!(submit(&mut algorithm, routers[routers.len() - 1], message(1, 0)), Ok(()));
+            assert_eq!(verified(&algorithm, 1), Some(true));
+            assert!(is_processed(&algorithm, 1));
+
+            // every submitter behaved honestly
+            for router in routers.iter() {
+                assert_eq!(credibility(&algorithm, *router), 55);
+            }
+
+            assert_eq!(submit(&mut algorithm, routers[0], message(1, 0)), Err(SubmitError::AlreadyProcessed));
+        }
+
+        #[ink::test]
+        fn conflicting_hash_loses_against_the_threshold() {
+            let routers = five_routers();
+            let mut algorithm = setup(&routers);
+
+            announce(&mut algorithm, 1);
+
+            // 4 of 5 copies agree, which is 8000 of the credibility over the threshold 7000
+            for router in routers[..4].iter() {
+                assert_eq!(submit(&mut algorithm, *router, message(1, 0)), Ok(()));
+            }
+            assert_eq!(submit(&mut algorithm, routers[4], message(1, 1)), Ok(()));
+
+            assert_eq!(verified(&algorithm, 1), Some(true));
+            for router in routers[..4].iter() {
+                assert_eq!(credibility(&algorithm, *router), 55);
+            }
+            assert_eq!(credibility(&algorithm, routers[4]), 40);
+        }
+
+        #[ink::test]
+        fn one_credibility_adjusted_event_per_message() {
+            let routers = five_routers();
+            let mut algorithm = setup(&routers);
+
+            announce(&mut algorithm, 1);
+            for router in routers[..4].iter() {
+                assert_eq!(submit(&mut algorithm, *router, message(1, 0)), Ok(()));
+            }
+
+            let events = ink::env::test::recorded_events().count();
+            assert_eq!(submit(&mut algorithm, routers[4], message(1, 1)), Ok(()));
+
+            let emitted = events_since(events);
+            let adjusted: ink::prelude::vec::Vec<&CredibilityAdjusted> = emitted.iter().filter_map(|event| match event {
+                Event::CredibilityAdjusted(adjusted) => Some(adjusted),
+                _ => None,
+            }).collect();
+
+            // all the submitters are adjusted in a single event
+            assert_eq!(adjusted.len(), 1);
+            assert_eq!(adjusted[0].msg_id, 1);
+            assert_eq!(adjusted[0].adjustments.len(), routers.len());
+            for adjustment in adjusted[0].adjustments.iter() {
+                assert_eq!(adjustment.honest, adjustment.router != routers[4]);
+                assert_eq!(adjustment.cred_before, 50);
+                assert_eq!(adjustment.cred_after, credibility(&algorithm, adjustment.router));
+            }
+        }
+
+        #[ink::test]
+        fn split_copies_do_not_reach_the_threshold() {
+            let routers = five_routers();
+            let mut algorithm = setup(&routers);
+
+            announce(&mut algorithm, 1);
+
+            // 3 of 5 copies agree, which is 6000 of the credibility below the threshold 7000
+            for router in routers[..3].iter() {
+                assert_eq!(submit(&mut algorithm, *router, message(1, 0)), Ok(()));
+            }
+            for router in routers[3..].iter() {
+                assert_eq!(submit(&mut algorithm, *router, message(1, 1)), Ok(()));
+            }
+
+            assert_eq!(verified(&algorithm, 1), Some(false));
+            assert!(is_processed(&algorithm, 1));
+
+            // the routers are not evaluated if no copy is accepted
+            for router in routers.iter() {
+                assert_eq!(credibility(&algorithm, *router), 50);
+            }
+        }
+
+        #[ink::test]
+        fn conflicting_hash_wins_with_more_credibility() {
+            let routers = five_routers();
+            let mut algorithm = setup(&routers);
+
+            // the credibility of the last three routers decreases 50 -> 40 -> 32 -> 26 -> 21 -> 17
+            for router in routers[2..].iter() {
+                assert_eq!(algorithm.do_evil(*router, 5), Ok(()));
+                assert_eq!(credibility(&algorithm, *router), 17);
+            }
+            assert_eq!(algorithm.set_sysinfo(5, 6000), Ok(()));
+
+            announce(&mut algorithm, 1);
+            // the two copies of the first two routers have 100 of the total 151 credibility, which is 6622
+            for router in routers[..2].iter() {
+                assert_eq!(submit(&mut algorithm, *router, message(1, 1)), Ok(()));
+            }
+            for router in routers[2..].iter() {
+                assert_eq!(submit(&mut algorithm, *router, message(1, 0)), Ok(()));
+            }
+
+            assert_eq!(verified(&algorithm, 1), Some(true));
+            for router in routers[..2].iter() {
+                assert_eq!(credibility(&algorithm, *router), 55);
+            }
+            for router in routers[2..].iter() {
+                assert_eq!(credibility(&algorithm, *router), 14);
+            }
+        }
+
+        #[ink::test]
+        fn single_copy_message_with_sqos() {
+            let routers = five_routers();
+            let mut algorithm = setup(&routers);
+
+            set_caller(AccountId::from(DESTINATION));
+            assert!(!algorithm.set_sqos(SQoSProfile {
+                copy_count: 1,
+                vf_threshold: 7000,
+                timeout: 100,
+                allow_single_copy: false,
+                min_partial_copies: 1,
+            }));
+            assert!(algorithm.set_sqos(SQoSProfile {
+                copy_count: 1,
+                vf_threshold: 7000,
+                timeout: 100,
+                allow_single_copy: true,
+                min_partial_copies: 1,
+            }));
+
+            set_caller(accounts().alice);
+            let selected = algorithm.announce_message(ink::prelude::string::String::from("ETHEREUM"), 1, AccountId::from(DESTINATION)).unwrap();
+            assert_eq!(selected.len(), 1);
+
+            assert_eq!(submit(&mut algorithm, selected[0], message(1, 0)), Ok(()));
+            assert_eq!(verified(&algorithm, 1), Some(true));
+            assert!(is_processed(&algorithm, 1));
+            assert_eq!(credibility(&algorithm, selected[0]), 55);
+        }
+
+        #[ink::test]
+        fn single_copy_fails_without_sqos_after_timeout() {
+            let routers = five_routers();
+            let mut algorithm = setup(&routers);
+
+            announce(&mut algorithm, 1);
+            assert_eq!(submit(&mut algorithm, routers[0], message(1, 0)), Ok(()));
+            assert_eq!(algorithm.finalize_expired(ink::prelude::string::String::from("ETHEREUM"), 1), Err(FinalizeError::NotExpired));
+
+            for _ in 0..algorithm.get_msg_timeout() {
+                ink::env::test::advance_block::<Environment>();
+            }
+
+            assert_eq!(submit(&mut algorithm, routers[1], message(1, 0)), Err(SubmitError::Expired));
+            assert_eq!(algorithm.finalize_expired(ink::prelude::string::String::from("ETHEREUM"), 1), Ok(false));
+
+            let (messages, _) = algorithm.simu_get_message(None, 100);
+            assert!(messages[0].processed && messages[0].failed);
+            assert_eq!(credibility(&algorithm, routers[0]), 50);
+        }
+
+        #[ink::test]
+        fn partial_verification_needs_min_partial_copies() {
+            let routers = five_routers();
+            let mut algorithm = setup(&routers);
+            let from_chain = ink::prelude::string::String::from("ETHEREUM");
+
+            // the copies delivered before the timeout
+            for (msg_id, copies) in [(1u128, 2usize), (2, 3), (3, 4)] {
+                announce(&mut algorithm, msg_id);
+                for router in routers[..copies].iter() {
+                    assert_eq!(submit(&mut algorithm, *router, message(msg_id, 0)), Ok(()));
+                }
+            }
+            for _ in 0..algorithm.get_msg_timeout() {
+                ink::env::test::advance_block::<Environment>();
+            }
+
+            set_caller(AccountId::from(DESTINATION));
+            assert!(!algorithm.set_sqos(SQoSProfile {
+                copy_count: 5,
+                vf_threshold: 7000,
+                timeout: algorithm.get_msg_timeout(),
+                allow_single_copy: false,
+                min_partial_copies: 6,
+            }));
+            assert!(algorithm.set_sqos(SQoSProfile {
+                copy_count: 5,
+                vf_threshold: 7000,
+                timeout: algorithm.get_msg_timeout(),
+                allow_single_copy: false,
+                min_partial_copies: 3,
+            }));
+
+            assert_eq!(algorithm.finalize_expired(from_chain.clone(), 1), Ok(false));
+            assert_eq!(verified(&algorithm, 1), None);
+            assert_eq!(algorithm.finalize_expired(from_chain.clone(), 2), Ok(true));
+            assert_eq!(verified(&algorithm, 2), Some(true));
+
+            // no partial verification without SQoS settings
+            algorithm.remove_sqos();
+            assert_eq!(algorithm.get_sqos(AccountId::from(DESTINATION)).min_partial_copies, 5);
+            assert_eq!(algorithm.finalize_expired(from_chain, 3), Ok(false));
+            assert_eq!(verified(&algorithm, 3), None);
+
+            let (messages, _) = algorithm.simu_get_message(None, 100);
+            let failed: ink::prelude::vec::Vec<(u128, bool)> = messages.iter().map(|ele| (ele.msg_id, ele.failed)).collect();
+            assert_eq!(failed, ink::prelude::vec![(1, true), (2, false), (3, true)]);
+        }
+
+        #[ink::test]
+        fn held_message_fails_after_the_timeout() {
+            let accounts = accounts();
+            let mut routers = five_routers();
+            routers.push(accounts.alice);
+            let mut algorithm = setup(&routers);
+            assert_eq!(algorithm.set_sysinfo(3, 7000), Ok(()));
+            assert_eq!(algorithm.set_inspection_params(10000, 2), Ok(()));
+
+            let selected = announce(&mut algorithm, 1);
+            for router in selected.iter() {
+                assert_eq!(submit(&mut algorithm, *router, message(1, 0)), Ok(()));
+            }
+
+            // one of the two inspectors submits its copy before the timeout
+            let inspectors = algorithm.get_selection(ink::prelude::string::String::from("ETHEREUM"), 1).unwrap().inspectors;
+            assert_eq!(inspectors.len(), 2);
+            assert_eq!(submit(&mut algorithm, inspectors[0], message(1, 0)), Ok(()));
+            assert!(algorithm.simu_get_message(None, 100).0[0].held);
+
+            for _ in 0..algorithm.get_msg_timeout() {
+                ink::env::test::advance_block::<Environment>();
+            }
+
+            assert_eq!(submit(&mut algorithm, inspectors[1], message(1, 0)), Err(SubmitError::Expired));
+            let events = ink::env::test::recorded_events().count();
+            assert_eq!(algorithm.finalize_expired(ink::prelude::string::String::from("ETHEREUM"), 1), Ok(false));
+            // `MessageReleased` and `MessageExpired`
+            assert_eq!(ink::env::test::recorded_events().count(), events + 2);
+
+            let (messages, _) = algorithm.simu_get_message(None, 100);
+            assert!(messages[0].processed && messages[0].failed);
+            assert_eq!(verified(&algorithm, 1), None);
+            for router in routers.iter() {
+                assert_eq!(credibility(&algorithm, *router), 50);
+            }
+        }
+
+        #[ink::test]
+        fn inspection_holds_and_releases_the_message() {
+            let accounts = accounts();
+            let mut routers = five_routers();
+            routers.push(accounts.alice);
+            let mut algorithm = setup(&routers);
+            assert_eq!(algorithm.set_sysinfo(3, 7000), Ok(()));
+            assert_eq!(algorithm.set_inspection_params(10000, 2), Ok(()));
+
+            let selected = announce(&mut algorithm, 1);
+            for router in selected[..2].iter() {
+                assert_eq!(submit(&mut algorithm, *router, message(1, 0)), Ok(()));
+            }
+
+            // the last copy holds the message, and the inspectors are selected
+            let events = ink::env::test::recorded_events().count();
+            assert_eq!(submit(&mut algorithm, selected[2], message(1, 0)), Ok(()));
+            let emitted = events_since(events);
+            assert_eq!(emitted.len(), 2);
+            assert!(matches!(&emitted[0], Event::MessageHeld(held) if held.inspection_copy_count == 2));
+            assert!(matches!(&emitted[1], Event::RoutersSelected(selection) if selection.inspection));
+            assert_eq!(verified(&algorithm, 1), None);
+
+            let inspectors = algorithm.get_selection(ink::prelude::string::String::from("ETHEREUM"), 1).unwrap().inspectors;
+            assert_eq!(inspectors.len(), 2);
+            assert!(inspectors.iter().all(|ele| !selected.contains(ele)));
+
+            // only the inspectors can submit copies during the inspection round
+            let idle = *routers.iter().find(|ele| !selected.contains(ele) && !inspectors.contains(ele)).unwrap();
+            assert_eq!(submit(&mut algorithm, idle, message(1, 0)), Err(SubmitError::NotSelected));
+            assert_eq!(submit(&mut algorithm, selected[0], message(1, 0)), Err(SubmitError::DuplicateSubmission));
+
+            assert_eq!(submit(&mut algorithm, inspectors[0], message(1, 0)), Ok(()));
+            assert!(algorithm.simu_get_message(None, 100).0[0].held);
+
+            // the last inspector releases the message
+            let events = ink::env::test::recorded_events().count();
+            assert_eq!(submit(&mut algorithm, inspectors[1], message(1, 0)), Ok(()));
+            let emitted = events_since(events);
+            assert_eq!(emitted.iter().filter(|event| matches!(event, Event::MessageReleased(released) if released.vf_passed)).count(), 1);
+            assert_eq!(verified(&algorithm, 1), Some(true));
+            assert!(is_processed(&algorithm, 1));
+
+            // the inspectors are evaluated with the selected routers
+            for router in selected.iter().chain(inspectors.iter()) {
+                assert_eq!(credibility(&algorithm, *router), 55);
+            }
+            assert_eq!(credibility(&algorithm, idle), 50);
+        }
+
+        #[ink::test]
+        fn credibility_curves_at_the_bounds() {
+            let bob = accounts().bob;
+            let mut algorithm = setup(&[bob]);
+            let piecewise = algorithm.get_evaluation_params();
+
+            // the middle credibility
+            assert_eq!(credibility(&algorithm, bob), 50);
+            assert_eq!(algorithm.do_honest_once(bob), Ok(()));
+            assert_eq!(credibility(&algorithm, bob), 55);
+            assert_eq!(algorithm.do_evil_once(bob), Ok(()));
+            assert_eq!(credibility(&algorithm, bob), 44);
+
+            // move to the bounds in one step with `ExponentialDecay`
+            assert_eq!(algorithm.set_evaluation_params(EvaluationParams {
+                model: EvaluationModel::ExponentialDecay,
+                gain_bps: 10000,
+                decay_bps: 10000,
+                ..piecewise.clone()
+            }), Ok(()));
+
+            assert_eq!(algorithm.do_honest_once(bob), Ok(()));
+            assert_eq!(credibility(&algorithm, bob), 100);
+            assert_eq!(algorithm.set_evaluation_params(piecewise.clone()), Ok(()));
+            // nothing to gain at the max credibility
+            assert_eq!(algorithm.do_honest_once(bob), Ok(()));
+            assert_eq!(credibility(&algorithm, bob), 100);
+            assert_eq!(algorithm.do_evil_once(bob), Ok(()));
+            assert_eq!(credibility(&algorithm, bob), 80);
+
+            assert_eq!(algorithm.set_evaluation_params(EvaluationParams {
+                model: EvaluationModel::ExponentialDecay,
+                gain_bps: 10000,
+                decay_bps: 10000,
+                ..piecewise.clone()
+            }), Ok(()));
+
+            assert_eq!(algorithm.do_evil_once(bob), Ok(()));
+            assert_eq!(credibility(&algorithm, bob), 0);
+            // a router without credibility cannot be selected
+            assert_eq!(algorithm.selection_test(1), None);
+
+            assert_eq!(algorithm.set_evaluation_params(piecewise), Ok(()));
+            // the piecewise curve cannot leave the min credibility
+            assert_eq!(algorithm.do_honest_once(bob), Ok(()));
+            assert_eq!(credibility(&algorithm, bob), 0);
+            assert_eq!(algorithm.do_evil_once(bob), Ok(()));
+            assert_eq!(credibility(&algorithm, bob), 0);
+        }
+
+        #[ink::test]
+        fn evaluation_requires_the_operator_role() {
+            let accounts = accounts();
+            let mut algorithm = setup(&[accounts.bob]);
+
+            set_caller(accounts.bob);
+            assert_eq!(algorithm.do_evil_once(accounts.bob), Err(AdminError::Unauthorized));
 
+            set_caller(accounts.alice);
+            assert_eq!(algorithm.grant_role(accounts.bob, Role::Operator), Ok(()));
+
+            set_caller(accounts.bob);
+            assert_eq!(algorithm.do_evil_once(accounts.bob), Ok(()));
+            assert_eq!(credibility(&algorithm, accounts.bob), 40);
+        }
+
+        #[ink::test]
+        fn ownership_is_transferred_after_acceptance() {
+            let accounts = accounts();
+            let mut algorithm = setup(&[]);
+
+            set_caller(accounts.bob);
+            assert_eq!(algorithm.transfer_ownership(accounts.bob), Err(AdminError::NotOwner));
+
+            set_caller(accounts.alice);
+            assert_eq!(algorithm.transfer_ownership(accounts.bob), Ok(()));
+            assert_eq!(algorithm.get_owner(), (accounts.alice, Some(accounts.bob)));
+
+            // the ownership is not transferred until the pending owner accepts it
+            assert!(!algorithm.has_role(accounts.bob, Role::Admin));
+            set_caller(accounts.charlie);
+            assert_eq!(algorithm.accept_ownership(), Err(AdminError::NotPendingOwner));
+
+            set_caller(accounts.bob);
+            let events = ink::env::test::recorded_events().count();
+            assert_eq!(algorithm.accept_ownership(), Ok(()));
+            let emitted = events_since(events);
+            assert_eq!(emitted.len(), 1);
+            assert!(matches!(&emitted[0], Event::OwnershipTransferred(transferred) if (transferred.previous == accounts.alice) && (transferred.new == accounts.bob)));
+            assert_eq!(algorithm.get_owner(), (accounts.bob, None));
+
+            // the previous owner loses the roles it had as the owner
+            assert!(algorithm.has_role(accounts.bob, Role::Admin));
+            assert!(!algorithm.has_role(accounts.alice, Role::Admin));
+            assert_eq!(algorithm.accept_ownership(), Err(AdminError::NotPendingOwner));
+
+            set_caller(accounts.alice);
+            assert_eq!(algorithm.transfer_ownership(accounts.alice), Err(AdminError::NotOwner));
+            assert_eq!(algorithm.set_sysinfo(3, 7000), Err(AdminError::Unauthorized));
+        }
+
+        #[ink::test]
+        fn roles_are_granted_and_revoked_by_the_owner() {
+            let accounts = accounts();
+            let mut algorithm = setup(&[]);
+
+            set_caller(accounts.bob);
+            assert_eq!(algorithm.grant_role(accounts.bob, Role::Admin), Err(AdminError::NotOwner));
+            assert_eq!(algorithm.set_sysinfo(3, 7000), Err(AdminError::Unauthorized));
+
+            set_caller(accounts.alice);
+            let events = ink::env::test::recorded_events().count();
+            assert_eq!(algorithm.grant_role(accounts.bob, Role::Admin), Ok(()));
+            let emitted = events_since(events);
+            assert!(matches!(&emitted[..], [Event::RoleChanged(changed)] if (changed.account == accounts.bob) && (changed.role == Role::Admin) && changed.granted));
+            assert!(algorithm.has_role(accounts.bob, Role::Admin));
+            assert!(!algorithm.has_role(accounts.bob, Role::Operator));
+
+            set_caller(accounts.bob);
+            assert_eq!(algorithm.set_sysinfo(3, 7000), Ok(()));
+            // only the owner grants roles
+            assert_eq!(algorithm.grant_role(accounts.charlie, Role::Admin), Err(AdminError::NotOwner));
+            assert_eq!(algorithm.revoke_role(accounts.bob, Role::Admin), Err(AdminError::NotOwner));
+
+            set_caller(accounts.alice);
+            let events = ink::env::test::recorded_events().count();
+            assert_eq!(algorithm.revoke_role(accounts.bob, Role::Admin), Ok(()));
+            let emitted = events_since(events);
+            assert!(matches!(&emitted[..], [Event::RoleChanged(changed)] if (changed.account == accounts.bob) && !changed.granted));
+            assert!(!algorithm.has_role(accounts.bob, Role::Admin));
+
+            set_caller(accounts.bob);
+            assert_eq!(algorithm.set_sysinfo(5, 7000), Err(AdminError::Unauthorized));
+        }
+
+        #[ink::test]
+        fn slash_fraction_is_set_by_an_admin() {
+            let accounts = accounts();
+            let mut algorithm = setup(&[]);
+            assert_eq!(algorithm.get_slash_fraction(), 1000);
+
+            set_caller(accounts.bob);
+            assert_eq!(algorithm.set_slash_fraction(5000), Err(AdminError::Unauthorized));
+
+            set_caller(accounts.alice);
+            assert_eq!(algorithm.set_slash_fraction(10001), Err(AdminError::ParamInvalid));
+            assert_eq!(algorithm.set_slash_fraction(5000), Ok(()));
+            assert_eq!(algorithm.get_slash_fraction(), 5000);
+
+            // encoded as `SlashAmount::Fraction(5000)` of `Tokenomics`
+            assert_eq!(scale::Encode::encode(&TokenomicsSlashAmount::Fraction(5000)), [0, 0x88, 0x13, 0, 0]);
+        }
+
+        #[ink::test]
+        fn require_stake_excludes_routers_and_weights_refresh_by_page() {
+            let routers = five_routers();
+            let mut algorithm = setup(&routers);
+            let weights = |algorithm: &AlgorithmPrototype| -> ink::prelude::vec::Vec<u128> {
+                algorithm.get_selection_weights(1, 10).iter().map(|ele| ele.1).collect()
+            };
+
+            // the credibility multiplied by `cred_ratio`
+            assert_eq!(weights(&algorithm), ink::prelude::vec![500000; 5]);
+
+            let policy = WeightPolicy {
+                cred_ratio: 10000,
+                stake_ratio: 0,
+                require_stake: true,
+            };
+
+            set_caller(routers[0]);
+            assert_eq!(algorithm.set_weight_policy(policy.clone()), Err(AdminError::Unauthorized));
+
+            set_caller(accounts().alice);
+            assert_eq!(algorithm.set_weight_policy(WeightPolicy { cred_ratio: 0, ..policy.clone() }), Err(AdminError::ParamInvalid));
+            assert_eq!(algorithm.set_weight_policy(WeightPolicy { stake_ratio: 10001, ..policy.clone() }), Err(AdminError::ParamInvalid));
+            assert_eq!(algorithm.set_weight_policy(policy.clone()), Ok(()));
+            assert_eq!(algorithm.get_weight_policy(), (None, policy));
+
+            // the cached weights are kept until they are refreshed
+            assert_eq!(weights(&algorithm), ink::prelude::vec![500000; 5]);
+
+            // no router has staked without `Tokenomics`
+            let next = algorithm.refresh_weights(None, 2);
+            assert!(next.is_some());
+            assert_eq!(weights(&algorithm).iter().filter(|weight| **weight == 0).count(), 2);
+            assert!(algorithm.refresh_weights(next, 2).is_some());
+            assert_eq!(weights(&algorithm).iter().filter(|weight| **weight == 0).count(), 4);
+
+            let next = algorithm.refresh_weights(None, 4);
+            assert_eq!(algorithm.refresh_weights(next, 4), None);
+            assert_eq!(weights(&algorithm), ink::prelude::vec![0; 5]);
+            assert_eq!(algorithm.selection_test(1), None);
+
+            assert_eq!(algorithm.set_weight_policy(WeightPolicy {
+                cred_ratio: 10000,
+                stake_ratio: 0,
+                require_stake: false,
+            }), Ok(()));
+            assert_eq!(algorithm.refresh_weights(None, 10), None);
+            assert_eq!(weights(&algorithm), ink::prelude::vec![500000; 5]);
+            assert_eq!(algorithm.selection_test(5).map(|selected| selected.len()), Some(5));
+        }
+
+        /// The compressed public key of the VRF oracle in the tests
+        const VRF_KEY: [u8; 33] = [
+            0x03, 0x08, 0x1f, 0xc1, 0x24, 0x8a, 0xab, 0xac, 0x96, 0x64, 0x8e, 0x75, 0x2a, 0x69, 0x8e, 0xce,
+            0x3d, 0x67, 0x35, 0x53, 0xa7, 0xc0, 0x91, 0x54, 0xab, 0x3a, 0x49, 0xc5, 0xa6, 0x7b, 0x1c, 0x76,
+            0x7e,
+        ];
+
+        /// The low-S signature of the VRF oracle over `blake2x256(scale::Encode(("ETHEREUM", 1), 0))`
+        const VRF_SELECTION_SIGNATURE: [u8; 65] = [
+            0xb9, 0xb6, 0xc0, 0xf7, 0x8e, 0xac, 0xaa, 0x2d, 0x33, 0xd8, 0x33, 0x38, 0x9e, 0x53, 0xb0, 0x85,
+            0xac, 0xa5, 0x0a, 0x14, 0x0c, 0xa9, 0x7b, 0x0f, 0x44, 0x7a, 0x93, 0x09, 0xda, 0x62, 0x52, 0xe1,
+            0x34, 0x36, 0xd6, 0x5c, 0x54, 0x5d, 0x66, 0x53, 0x4e, 0xb1, 0xd5, 0xa2, 0x0b, 0x8b, 0x2f, 0x66,
+            0x32, 0x60, 0x01, 0x8b, 0x7a, 0x2a, 0xe5, 0xbb, 0x89, 0x88, 0xfc, 0x24, 0x73, 0x97, 0x4a, 0x16,
+            0x01,
+        ];
+
+        /// The low-S signature of the VRF oracle over `blake2x256(scale::Encode(("ETHEREUM", 1), 1))`
+        const VRF_INSPECTION_SIGNATURE: [u8; 65] = [
+            0xf3, 0x1b, 0xe7, 0x84, 0x9d, 0xa6, 0xc2, 0x1a, 0x51, 0x6f, 0xd4, 0x56, 0x8f, 0x05, 0x2a, 0x5c,
+            0x86, 0xd7, 0x41, 0xe9, 0x79, 0x77, 0x02, 0x65, 0x11, 0x7a, 0xed, 0xd6, 0x45, 0xf5, 0x9a, 0xe1,
+            0x07, 0xbe, 0x92, 0x80, 0x57, 0x11, 0x70, 0x66, 0xa4, 0x31, 0x33, 0xa5, 0xfe, 0x04, 0xde, 0xf9,
+            0xe6, 0x9f, 0x01, 0xd0, 0x13, 0x22, 0xa7, 0xe9, 0x86, 0xb6, 0x38, 0x33, 0x82, 0x7a, 0x16, 0xcf,
+            0x00,
+        ];
+
+        #[ink::test]
+        fn vrf_output_is_supplied_by_the_oracle() {
+            let mut algorithm = setup(&five_routers());
+            assert_eq!(algorithm.set_randomness_source(RandomnessSource::Vrf, Some(VRF_KEY)), Ok(()));
+
+            // the same signature with `n - s`, which is recovered to the same key
+            const HIGH_S_SIGNATURE: [u8; 65] = [
+                0xb9, 0xb6, 0xc0, 0xf7, 0x8e, 0xac, 0xaa, 0x2d, 0x33, 0xd8, 0x33, 0x38, 0x9e, 0x53, 0xb0, 0x85,
+                0xac, 0xa5, 0x0a, 0x14, 0x0c, 0xa9, 0x7b, 0x0f, 0x44, 0x7a, 0x93, 0x09, 0xda, 0x62, 0x52, 0xe1,
+                0xcb, 0xc9, 0x29, 0xa3, 0xab, 0xa2, 0x99, 0xac, 0xb1, 0x4e, 0x2a, 0x5d, 0xf4, 0x74, 0xd0, 0x98,
+                0x88, 0x4e, 0xdb, 0x5b, 0x35, 0x1d, 0xba, 0x80, 0x36, 0x49, 0x62, 0x68, 0x5c, 0x9e, 0xf7, 0x2b,
+                0x00,
+            ];
+            let from_chain = ink::prelude::string::String::from("ETHEREUM");
+            let oracle = AccountId::from(randomness::blake2(&VRF_KEY));
+
+            assert_eq!(algorithm.announce_message(from_chain.clone(), 1, AccountId::from(DESTINATION)), Err(SubmitError::RandomnessNotReady));
+            assert_eq!(algorithm.supply_vrf(from_chain.clone(), 1, ROUND_SELECTION, VRF_SELECTION_SIGNATURE), Err(RandomnessError::NotOracle));
+
+            set_caller(oracle);
+            assert_eq!(algorithm.supply_vrf(from_chain.clone(), 1, ROUND_SELECTION, HIGH_S_SIGNATURE), Err(RandomnessError::HighS));
+            // signed for the other round
+            assert_eq!(algorithm.supply_vrf(from_chain.clone(), 1, ROUND_INSPECTION, VRF_SELECTION_SIGNATURE), Err(RandomnessError::InvalidProof));
+
+            assert_eq!(algorithm.supply_vrf(from_chain.clone(), 1, ROUND_SELECTION, VRF_SELECTION_SIGNATURE), Ok(()));
+            assert_eq!(algorithm.supply_vrf(from_chain.clone(), 1, ROUND_SELECTION, VRF_SELECTION_SIGNATURE), Err(RandomnessError::AlreadySupplied));
+
+            set_caller(accounts().alice);
+            assert_eq!(algorithm.announce_message(from_chain, 1, AccountId::from(DESTINATION)).map(|selected| selected.len()), Ok(5));
+        }
+
+        #[ink::test]
+        fn vrf_inspection_waits_for_the_oracle() {
+            let accounts = accounts();
+            let mut routers = five_routers();
+            routers.push(accounts.alice);
+            let mut algorithm = setup(&routers);
+            assert_eq!(algorithm.set_randomness_source(RandomnessSource::Vrf, Some(VRF_KEY)), Ok(()));
+            assert_eq!(algorithm.set_inspection_params(10000, 1), Ok(()));
+
+            let from_chain = ink::prelude::string::String::from("ETHEREUM");
+            let oracle = AccountId::from(randomness::blake2(&VRF_KEY));
+            set_caller(oracle);
+            assert_eq!(algorithm.supply_vrf(from_chain.clone(), 1, ROUND_SELECTION, VRF_SELECTION_SIGNATURE), Ok(()));
+
+            let selected = announce(&mut algorithm, 1);
+            for router in selected[..4].iter() {
+                assert_eq!(submit(&mut algorithm, *router, message(1, 0)), Ok(()));
+            }
+
+            // the message is held without inspectors until the oracle supplies the output of the inspection
+            let events = ink::env::test::recorded_events().count();
+            assert_eq!(submit(&mut algorithm, selected[4], message(1, 0)), Ok(()));
+            let emitted = events_since(events);
+            assert!(matches!(&emitted[..], [Event::MessageHeld(held)] if held.inspection_copy_count == 0));
+            assert!(algorithm.get_selection(from_chain.clone(), 1).unwrap().inspectors.is_empty());
+
+            let idle = *routers.iter().find(|ele| !selected.contains(ele)).unwrap();
+            assert_eq!(submit(&mut algorithm, idle, message(1, 0)), Err(SubmitError::NotSelected));
+
+            // the output of the inspection starts the inspection round
+            set_caller(oracle);
+            let events = ink::env::test::recorded_events().count();
+            assert_eq!(algorithm.supply_vrf(from_chain.clone(), 1, ROUND_INSPECTION, VRF_INSPECTION_SIGNATURE), Ok(()));
+            let emitted = events_since(events);
+            assert_eq!(emitted.len(), 3);
+            assert!(matches!(&emitted[0], Event::RandomnessSupplied(supplied) if supplied.round == ROUND_INSPECTION));
+            assert!(matches!(&emitted[1], Event::MessageHeld(held) if held.inspection_copy_count == 1));
+            assert!(matches!(&emitted[2], Event::RoutersSelected(selection) if selection.inspection));
+            assert_eq!(algorithm.get_selection(from_chain, 1).unwrap().inspectors, ink::prelude::vec![idle]);
+
+            let events = ink::env::test::recorded_events().count();
+            assert_eq!(submit(&mut algorithm, idle, message(1, 0)), Ok(()));
+            assert_eq!(events_since(events).iter().filter(|event| matches!(event, Event::MessageReleased(released) if released.vf_passed)).count(), 1);
+            assert_eq!(verified(&algorithm, 1), Some(true));
+        }
+
+        #[ink::test]
+        fn commit_reveal_updates_the_beacon() {
+            let accounts = accounts();
+            let mut algorithm = setup(&[accounts.bob]);
+            let secret = [7; 32];
+
+            set_caller(accounts.bob);
+            assert_eq!(algorithm.commit_randomness(randomness::commitment(&secret)), Err(RandomnessError::WrongSource));
+
+            set_caller(accounts.alice);
+            assert_eq!(algorithm.set_randomness_source(RandomnessSource::CommitReveal, None), Ok(()));
+            let (_, _, beacon) = algorithm.get_randomness_source();
+
+            set_caller(accounts.charlie);
+            assert_eq!(algorithm.commit_randomness(randomness::commitment(&secret)), Err(RandomnessError::NotRouter));
+
+            set_caller(accounts.bob);
+            assert_eq!(algorithm.reveal_randomness(secret), Err(RandomnessError::NoCommitment));
+            assert_eq!(algorithm.commit_randomness(randomness::commitment(&secret)), Ok(()));
+            assert_eq!(algorithm.commit_randomness(randomness::commitment(&[8; 32])), Err(RandomnessError::AlreadyCommitted));
+            assert_eq!(algorithm.reveal_randomness(secret), Err(RandomnessError::TooEarly));
+
+            ink::env::test::advance_block::<Environment>();
+            assert_eq!(algorithm.reveal_randomness([8; 32]), Err(RandomnessError::InvalidProof));
+
+            let events = ink::env::test::recorded_events().count();
+            assert_eq!(algorithm.reveal_randomness(secret), Ok(()));
+            let mixed = randomness::mix(&beacon, &secret);
+            let emitted = events_since(events);
+            assert!(matches!(&emitted[..], [Event::BeaconUpdated(updated)] if (updated.router == accounts.bob) && (updated.beacon == mixed)));
+            assert_eq!(algorithm.get_randomness_source().2, mixed);
+
+            // the commitment is consumed by the reveal
+            assert_eq!(algorithm.reveal_randomness(secret), Err(RandomnessError::NoCommitment));
+            assert_eq!(algorithm.commit_randomness(randomness::commitment(&secret)), Ok(()));
+        }
+
+        /// The compressed public key of the router signing the fraud copies in the tests
+        const FRAUD_ROUTER_KEY: [u8; 33] = [
+            0x03, 0xb2, 0xa9, 0x87, 0x97, 0x48, 0x3a, 0xca, 0xcb, 0xc0, 0x29, 0x92, 0x02, 0x16, 0xec, 0x6c,
+            0x65, 0x42, 0x0c, 0xb8, 0xe4, 0x66, 0x7a, 0x3b, 0x11, 0xcf, 0xfc, 0x7b, 0x3f, 0x3b, 0x2c, 0x63,
+            0xc8,
+        ];
+
+        /// The signature of `FRAUD_ROUTER_KEY` over the keccak256 hash of `message(1, 1)`
+        const FRAUD_SIGNATURE: [u8; 65] = [
+            0x60, 0x9c, 0x08, 0xd4, 0x5b, 0xb6, 0x74, 0xe4, 0xdf, 0xb3, 0xd7, 0x37, 0xd8, 0x47, 0xdf, 0x55,
+            0xda, 0x07, 0x74, 0xb4, 0xa0, 0x3e, 0x95, 0xac, 0x07, 0xb5, 0x50, 0x61, 0x4c, 0x2a, 0xe3, 0xf4,
+            0x5f, 0x34, 0xad, 0xa8, 0x2d, 0x54, 0xf0, 0x7a, 0x4b, 0x8f, 0xf6, 0x48, 0x02, 0xf6, 0x0e, 0x91,
+            0xfe, 0x7f, 0xa6, 0x13, 0xf6, 0xf9, 0x88, 0xac, 0xa7, 0xec, 0x38, 0x2e, 0xdd, 0xd4, 0x87, 0x1a,
+            0x01,
+        ];
+
+        /// The signature of another key over the keccak256 hash of `message(1, 1)`
+        const WRONG_KEY_SIGNATURE: [u8; 65] = [
+            0xc0, 0x2f, 0xbc, 0x8a, 0xb2, 0xeb, 0xfc, 0x70, 0xc9, 0x8e, 0x34, 0xa0, 0x45, 0x9a, 0x6c, 0x36,
+            0x26, 0xea, 0xe4, 0xd1, 0xd9, 0x37, 0xd8, 0x90, 0xd6, 0x41, 0x8b, 0xce, 0x03, 0xb8, 0xea, 0xa4,
+            0x26, 0x1e, 0xc5, 0x90, 0x2a, 0x50, 0xb1, 0x58, 0xab, 0xf3, 0x7d, 0x24, 0x00, 0xad, 0xe7, 0x2a,
+            0x38, 0xfb, 0x9a, 0x8e, 0xc9, 0xbe, 0xee, 0x0e, 0x6c, 0x7e, 0xc0, 0x6b, 0xa0, 0x5d, 0x20, 0xbe,
+            0x01,
+        ];
+
+        /// Verify `message(1, 0)` with the copies of the router of `FRAUD_ROUTER_KEY` and four other routers,
+        /// and return the router of `FRAUD_ROUTER_KEY`
+        fn verify_with_fraud_router() -> (AlgorithmPrototype, AccountId) {
+            let signer = AccountId::from(randomness::blake2(&FRAUD_ROUTER_KEY));
+            let mut routers = five_routers();
+            routers[4] = signer;
+            let mut algorithm = setup(&routers);
+
+            announce(&mut algorithm, 1);
+
+            for router in routers.iter() {
+                assert_eq!(submit(&mut algorithm, *router, message(1, 0)), Ok(()));
+            }
+            assert_eq!(verified(&algorithm, 1), Some(true));
+
+            (algorithm, signer)
+        }
+
+        #[ink::test]
+        fn fraud_proof_jails_the_signer() {
+            let (mut algorithm, signer) = verify_with_fraud_router();
+            assert_eq!(message(1, 1).into_hash::<ink::env::hash::Keccak256>(), [
+                0x47, 0x06, 0x9e, 0xf7, 0xdd, 0xbc, 0x0a, 0xa7, 0xc4, 0x9e, 0x90, 0x7d, 0x36, 0x14, 0x84, 0x09,
+                0xbd, 0xf0, 0x05, 0x01, 0xb6, 0x64, 0xca, 0x60, 0x14, 0x79, 0xa5, 0x8c, 0x68, 0xd7, 0x7b, 0xac,
+            ]);
+            let cred_before = credibility(&algorithm, signer);
+
+            let challenger = accounts().frank;
+            set_caller(challenger);
+            let events = ink::env::test::recorded_events().count();
+            assert_eq!(algorithm.submit_fraud_proof(message(1, 1), signer, FRAUD_SIGNATURE), Ok(()));
+            // `RouterSlashed`
+            assert_eq!(ink::env::test::recorded_events().count(), events + 1);
+
+            let router = algorithm.get_router(signer).unwrap();
+            assert_eq!(router.status, RouterStatus::Jailed);
+            assert!(router.credibility < cred_before);
+
+            let record = algorithm.get_slash_record(ink::prelude::string::String::from("ETHEREUM"), 1, signer).unwrap();
+            assert_eq!(record.challenger, challenger);
+            assert_eq!(record.fraud_hash, message(1, 1).into_hash::<ink::env::hash::Keccak256>());
+            assert_eq!((record.cred_before, record.cred_after), (cred_before, router.credibility));
+
+            assert_eq!(algorithm.submit_fraud_proof(message(1, 1), signer, FRAUD_SIGNATURE), Err(FraudProofError::AlreadySlashed));
+            assert_eq!(ink::env::test::recorded_events().count(), events + 1);
+        }
+
+        #[ink::test]
+        fn invalid_fraud_proofs_are_rejected() {
+            let (mut algorithm, signer) = verify_with_fraud_router();
+            let accounts = accounts();
+            let cred_before = credibility(&algorithm, signer);
+
+            set_caller(accounts.frank);
+            assert!(algorithm.register_router(ink::prelude::string::String::from("router")));
+
+            // the accepted copy itself
+            assert_eq!(algorithm.submit_fraud_proof(message(1, 0), signer, FRAUD_SIGNATURE), Err(FraudProofError::SameHash));
+            // `frank` registered after the message was verified
+            assert_eq!(algorithm.submit_fraud_proof(message(1, 1), accounts.frank, FRAUD_SIGNATURE), Err(FraudProofError::NotSubmitter));
+            // signed by another key, or signed by the router but blamed on another submitter
+            assert_eq!(algorithm.submit_fraud_proof(message(1, 1), signer, WRONG_KEY_SIGNATURE), Err(FraudProofError::InvalidSignature));
+            assert_eq!(algorithm.submit_fraud_proof(message(1, 1), accounts.bob, FRAUD_SIGNATURE), Err(FraudProofError::InvalidSignature));
+            assert_eq!(algorithm.submit_fraud_proof(message(2, 1), signer, FRAUD_SIGNATURE), Err(FraudProofError::NotVerified));
+
+            assert_eq!(credibility(&algorithm, signer), cred_before);
+            assert!(algorithm.get_slash_record(ink::prelude::string::String::from("ETHEREUM"), 1, signer).is_none());
+        }
+
+        #[ink::test]
+        fn submitters_of_the_rejected_copy_are_not_proven_again() {
+            let signer = AccountId::from(randomness::blake2(&FRAUD_ROUTER_KEY));
+            let mut routers = five_routers();
+            routers[4] = signer;
+            let mut algorithm = setup(&routers);
+
+            announce(&mut algorithm, 1);
+            for router in routers[..4].iter() {
+                assert_eq!(submit(&mut algorithm, *router, message(1, 0)), Ok(()));
+            }
+            assert_eq!(submit(&mut algorithm, signer, message(1, 1)), Ok(()));
+            assert_eq!(verified(&algorithm, 1), Some(true));
+            assert_eq!(credibility(&algorithm, signer), 40);
+
+            // the signed copy is the one evaluated as evil already
+            set_caller(accounts().frank);
+            assert_eq!(algorithm.submit_fraud_proof(message(1, 1), signer, FRAUD_SIGNATURE), Err(FraudProofError::NotSubmitter));
+            assert_eq!(credibility(&algorithm, signer), 40);
+            assert_eq!(algorithm.get_router(signer).unwrap().status, RouterStatus::Active);
+            assert!(algorithm.get_slash_record(ink::prelude::string::String::from("ETHEREUM"), 1, signer).is_none());
+        }
+
+        #[ink::test]
+        fn replay_gives_the_same_results() {
+            use algorithm_replay::scenario::{Network, Outcome, Params, Router, RouterStatus as ReplayStatus, Seed, Step};
+            use algorithm_replay::verification::MessageCopy;
+
+            let accounts = accounts();
+            let mut routers = five_routers();
+            routers.push(accounts.alice);
+            let mut algorithm = setup(&routers);
+            assert_eq!(algorithm.set_sysinfo(3, 7000), Ok(()));
+
+            // the routers are replayed in the order they are registered
+            let id = |router: &AccountId| algorithm_replay::to_hex(router.as_ref());
+            let mut network = Network::new(Params::default(), routers.iter().map(|router| Router {
+                id: id(router),
+                credibility: 50,
+                status: ReplayStatus::Active,
+                staking_weight: 0,
+                offences: 0,
+                last_offence: None,
+            }).collect());
+
+            // different credibilities so that the weights differ
+            for (times, router) in routers.iter().enumerate() {
+                assert_eq!(algorithm.do_evil(*router, times as u32), Ok(()));
+                network.apply(&Step::Evil { router: id(router), times: times as u32, block: 0 }).unwrap();
+            }
+
+            let selected = announce(&mut algorithm, 1);
+            let seed = algorithm.get_selection(ink::prelude::string::String::from("ETHEREUM"), 1).unwrap().seed;
+            let replayed = network.apply(&Step::Select {
+                seed: Seed::Raw(algorithm_replay::to_hex(&seed)),
+                n: 3,
+                excluded: ink::prelude::vec![],
+            }).unwrap();
+            assert_eq!(replayed, Outcome::Select {
+                seed: algorithm_replay::to_hex(&seed),
+                routers: selected.iter().map(id).collect(),
+            });
+
+            // the two routers with more credibility agree, and the other one submits a conflicting copy
+            let mut submitters = selected.clone();
+            submitters.sort_by_key(|router| core::cmp::Reverse(credibility(&algorithm, *router)));
+            let copies = [message(1, 0), message(1, 0), message(1, 1)];
+            for (router, copy) in submitters.iter().zip(copies.iter()) {
+                assert_eq!(submit(&mut algorithm, *router, copy.clone()), Ok(()));
+            }
+
+            let (verified_results, _) = algorithm.get_verified_results(None, 100);
+            let contract_sums: ink::prelude::vec::Vec<u128> = verified_results[0].submitted.iter().map(|ele| ele.cred_sum).collect();
+
+            let hash = |copy: &IReceivedMessage| algorithm_replay::to_hex(&copy.into_hash::<ink::env::hash::Keccak256>());
+            let replayed = network.apply(&Step::Verify {
+                copies: ink::prelude::vec![
+                    MessageCopy { hash: hash(&copies[0]), submitters: submitters[..2].iter().map(id).collect() },
+                    MessageCopy { hash: hash(&copies[2]), submitters: ink::prelude::vec![id(&submitters[2])] },
+                ],
+                block: 0,
+            }).unwrap();
+
+            let Outcome::Verify { verification, adjustments } = replayed else {
+                panic!("not a verification");
+            };
+            assert!(verification.vf_passed && verified_results[0].vf_passed);
+            assert_eq!(verification.cred_sums, contract_sums);
+            assert_eq!(adjustments.len(), 3);
+            for (router, replayed) in routers.iter().zip(network.routers().iter()) {
+                assert_eq!(credibility(&algorithm, *router), replayed.credibility);
+            }
+        }
+
+        /// Drop the credibility of `routers` to the min in one step with `ExponentialDecay`
+        fn drop_to_min(algorithm: &mut AlgorithmPrototype, routers: &[AccountId]) {
+            let piecewise = algorithm.get_evaluation_params();
+            assert_eq!(algorithm.set_evaluation_params(EvaluationParams {
+                model: EvaluationModel::ExponentialDecay,
+                decay_bps: 10000,
+                ..piecewise.clone()
+            }), Ok(()));
+
+            for router in routers.iter() {
+                assert_eq!(algorithm.do_evil_once(*router), Ok(()));
+                assert_eq!(credibility(algorithm, *router), 0);
+            }
+
+            assert_eq!(algorithm.set_evaluation_params(piecewise), Ok(()));
+        }
+
+        #[ink::test]
+        fn verification_without_credibility_does_not_pass() {
+            let routers = five_routers();
+            let mut algorithm = setup(&routers);
+            assert_eq!(algorithm.set_sysinfo(5, 0), Ok(()));
+
+            // the routers lose all their credibility after they are selected
+            set_caller(accounts().alice);
+            let selected = algorithm.announce_message(ink::prelude::string::String::from("ETHEREUM"), 1, AccountId::from(DESTINATION)).unwrap();
+            assert_eq!(selected.len(), 5);
+            drop_to_min(&mut algorithm, &routers);
+
+            for router in routers[..3].iter() {
+                assert_eq!(submit(&mut algorithm, *router, message(1, 0)), Ok(()));
+            }
+            for router in routers[3..].iter() {
+                assert_eq!(submit(&mut algorithm, *router, message(1, 1)), Ok(()));
+            }
+
+            // not even the threshold `0` is reached
+            assert_eq!(verified(&algorithm, 1), Some(false));
+            assert!(is_processed(&algorithm, 1));
+            for router in routers.iter() {
+                assert_eq!(credibility(&algorithm, *router), 0);
+            }
+        }
+
+        #[ink::test]
+        fn evaluation_below_the_min_credibility() {
+            let accounts = accounts();
+            let bob = accounts.bob;
+            let mut algorithm = setup(&[bob]);
+            assert_eq!(algorithm.get_credibility_range(), (0, 50, 100));
+
+            set_caller(bob);
+            assert_eq!(algorithm.set_credibility_range(60, 60, 100), Err(AdminError::Unauthorized));
+
+            // the floor of the evaluation params is below the new min
+            set_caller(accounts.alice);
+            assert_eq!(algorithm.set_credibility_range(60, 60, 100), Err(AdminError::ParamInvalid));
+            assert_eq!(algorithm.set_credibility_range(60, 50, 100), Err(AdminError::ParamInvalid));
+
+            // the min is raised above the credibility of the router
+            let params = algorithm.get_evaluation_params();
+            assert_eq!(algorithm.set_evaluation_params(EvaluationParams { floor: 60, ..params.clone() }), Ok(()));
+            assert_eq!(algorithm.set_credibility_range(60, 60, 100), Ok(()));
+            assert_eq!(credibility(&algorithm, bob), 50);
+
+            assert_eq!(algorithm.do_honest_once(bob), Ok(()));
+            assert_eq!(credibility(&algorithm, bob), 70);
+
+            assert_eq!(algorithm.set_evaluation_params(EvaluationParams { floor: 80, ..params }), Ok(()));
+            assert_eq!(algorithm.set_credibility_range(80, 80, 100), Ok(()));
+            assert_eq!(algorithm.do_evil_once(bob), Ok(()));
+            assert_eq!(credibility(&algorithm, bob), 80);
+            assert_eq!(algorithm.do_evil(bob, 3), Ok(()));
+            assert_eq!(credibility(&algorithm, bob), 80);
+        }
+
+        #[ink::test]
+        fn statistic_without_weights_is_none() {
+            let routers = five_routers();
+            let mut algorithm = setup(&[]);
+            assert_eq!(algorithm.selection_statistic(10), None);
+
+            for router in routers.iter() {
+                set_caller(*router);
+                assert!(algorithm.register_router(ink::prelude::string::String::from("router")));
+            }
+            set_caller(accounts().alice);
+            assert_eq!(algorithm.selection_statistic(10).map(|intervals| intervals.len()), Some(5));
+
+            drop_to_min(&mut algorithm, &routers);
+            assert_eq!(algorithm.selection_statistic(10), None);
+            assert_eq!(algorithm.selection_test(1), None);
+            // nothing is sampled
+            assert_eq!(algorithm.selection_statistic(0).map(|intervals| intervals.len()), Some(5));
+        }
     }
 }
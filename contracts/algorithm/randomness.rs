@@ -0,0 +1,208 @@
+//! Randomness sources of router selection
+//!
+//! Every selection is drawn from a 32-byte seed, which is provided by the configured `RandomnessSource`
+//! and recorded in the `RoutersSelected` event. With the seed, the selection can be re-computed off-chain by `SeededRandom`.
+
+use ink::env::hash::{Blake2x256, HashOutput};
+
+use crate::selection::RandomSource;
+
+/// The round of selecting the routers to deliver a message
+pub const ROUND_SELECTION: u8 = 0;
+/// The round of drawing the inspection of a delivered message and selecting the inspectors
+pub const ROUND_INSPECTION: u8 = 1;
+
+/// Random numbers derived from one seed for different purposes
+pub const DOMAIN_ROUTERS: u8 = 0;
+pub const DOMAIN_INSPECTION_DRAW: u8 = 1;
+pub const DOMAIN_INSPECTORS: u8 = 2;
+
+/// The providers of the selection seeds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+pub enum RandomnessSource {
+    /// The randomness of the runtime fetched by the `FetchRandom` chain extension, which can be predicted by the collators
+    #[default]
+    InkRandom,
+    /// A beacon mixed from the secrets committed and then revealed by the routers,
+    /// which is as unpredictable as the last reveal
+    CommitReveal,
+    /// The output supplied by an external oracle for every message and round,
+    /// which is the low-S ECDSA signature of the oracle checked on-chain against its public key.
+    /// It is not a real VRF: the oracle chooses the ECDSA nonce, so it can grind the signature until the seed suits it,
+    /// and it must be trusted not to bias the selection
+    Vrf,
+}
+
+/// The compressed ECDSA public key of the VRF oracle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+pub struct VrfKey(pub [u8; 33]);
+
+/// `StorageLayout` is implemented for arrays of at most 32 items, so the key is laid out as a leaf
+#[cfg(feature = "std")]
+impl ink::storage::traits::StorageLayout for VrfKey {
+    fn layout(key: &ink::primitives::Key) -> ink::metadata::layout::Layout {
+        ink::metadata::layout::Layout::Leaf(ink::metadata::layout::LeafLayout::from_key::<Self>(
+            ink::metadata::layout::LayoutKey::from(key),
+        ))
+    }
+}
+
+/// The randomness of the runtime, which takes the place of `random` removed from the environment in ink! 4.
+/// The runtime returns the output of its randomness pallet for `subject`
+#[ink::chain_extension]
+pub trait FetchRandom {
+    type ErrorCode = RandomReadErr;
+
+    #[ink(extension = 1101)]
+    fn fetch_random(subject: ink::prelude::vec::Vec<u8>) -> [u8; 32];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+pub enum RandomReadErr {
+    FailGetRandomSource,
+}
+
+impl ink::env::chain_extension::FromStatusCode for RandomReadErr {
+    fn from_status_code(status_code: u32) -> Result<(), Self> {
+        match status_code {
+            0 => Ok(()),
+            _ => Err(RandomReadErr::FailGetRandomSource),
+        }
+    }
+}
+
+/// The default environment with the `FetchRandom` chain extension
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+pub enum RandomnessEnvironment {}
+
+impl ink::env::Environment for RandomnessEnvironment {
+    const MAX_EVENT_TOPICS: usize = <ink::env::DefaultEnvironment as ink::env::Environment>::MAX_EVENT_TOPICS;
+
+    type AccountId = <ink::env::DefaultEnvironment as ink::env::Environment>::AccountId;
+    type Balance = <ink::env::DefaultEnvironment as ink::env::Environment>::Balance;
+    type Hash = <ink::env::DefaultEnvironment as ink::env::Environment>::Hash;
+    type BlockNumber = <ink::env::DefaultEnvironment as ink::env::Environment>::BlockNumber;
+    type Timestamp = <ink::env::DefaultEnvironment as ink::env::Environment>::Timestamp;
+
+    type ChainExtension = FetchRandom;
+}
+
+/// The half of the order of secp256k1 in big-endian, the `s` of a low-S signature is not above it
+const SECP256K1_HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// Whether the ECDSA `signature` (`r ++ s ++ v`) is in the low-S form.
+/// Both `s` and `n - s` are valid for the same key and message, only the low one is accepted so that
+/// a supplied output has a single signature
+pub fn is_low_s(signature: &[u8; 65]) -> bool {
+    signature[32..64] <= SECP256K1_HALF_ORDER[..]
+}
+
+pub fn blake2(input: &[u8]) -> [u8; 32] {
+    let mut output = <Blake2x256 as HashOutput>::Type::default();
+    ink::env::hash_bytes::<Blake2x256>(input, &mut output);
+    output
+}
+
+/// The commitment of a secret in commit-reveal
+pub fn commitment(secret: &[u8; 32]) -> [u8; 32] {
+    blake2(secret)
+}
+
+/// The hash of `seed` and `subject`, which is used to mix a revealed secret into the beacon,
+/// and to derive the seed of a message from the beacon
+pub fn mix(seed: &[u8; 32], subject: &[u8]) -> [u8; 32] {
+    let mut input = ink::prelude::vec::Vec::from(&seed[..]);
+    input.extend_from_slice(subject);
+    blake2(&input)
+}
+
+/// Random numbers expanded from a seed.
+/// Every block of 32 bytes is `blake2(seed ++ domain ++ counter)`, which gives two `u128` in big-endian
+pub struct SeededRandom {
+    seed: [u8; 32],
+    domain: u8,
+    counter: u32,
+    buffer: ink::prelude::vec::Vec<u128>,
+}
+
+impl SeededRandom {
+    pub fn new(seed: &[u8; 32], domain: u8) -> Self {
+        SeededRandom {
+            seed: *seed,
+            domain,
+            counter: 0,
+            buffer: ink::prelude::vec![],
+        }
+    }
+}
+
+impl RandomSource for SeededRandom {
+    fn next_u128(&mut self) -> u128 {
+        if self.buffer.is_empty() {
+            let mut subject = ink::prelude::vec![self.domain];
+            subject.extend_from_slice(&self.counter.to_be_bytes());
+            self.counter = self.counter.wrapping_add(1);
+
+            let block = mix(&self.seed, &subject);
+            // the buffer is popped from the end
+            for chunk in block.chunks_exact(16).rev() {
+                let mut sixteen_bytes = [0u8; 16];
+                sixteen_bytes.copy_from_slice(chunk);
+                self.buffer.push(u128::from_be_bytes(sixteen_bytes));
+            }
+        }
+
+        self.buffer.pop().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_random_is_reproducible() {
+        let seed = [7u8; 32];
+        let mut first = SeededRandom::new(&seed, DOMAIN_ROUTERS);
+        let mut second = SeededRandom::new(&seed, DOMAIN_ROUTERS);
+
+        let block = mix(&seed, &[DOMAIN_ROUTERS, 0, 0, 0, 0]);
+        let mut high = [0u8; 16];
+        high.copy_from_slice(&block[..16]);
+        assert_eq!(first.next_u128(), u128::from_be_bytes(high));
+        second.next_u128();
+
+        for _ in 0..10 {
+            assert_eq!(first.next_u128(), second.next_u128());
+        }
+    }
+
+    #[test]
+    fn domains_are_separated() {
+        let seed = [7u8; 32];
+        let mut routers = SeededRandom::new(&seed, DOMAIN_ROUTERS);
+        let mut inspectors = SeededRandom::new(&seed, DOMAIN_INSPECTORS);
+
+        assert_ne!(routers.next_u128(), inspectors.next_u128());
+    }
+
+    #[test]
+    fn only_low_s_is_accepted() {
+        let mut signature = [0u8; 65];
+        signature[32..64].copy_from_slice(&SECP256K1_HALF_ORDER);
+        assert!(is_low_s(&signature));
+
+        signature[63] += 1;
+        assert!(!is_low_s(&signature));
+
+        signature[32..64].copy_from_slice(&[0xff; 32]);
+        assert!(!is_low_s(&signature));
+    }
+}
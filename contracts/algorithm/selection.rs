@@ -0,0 +1,152 @@
+//! Weighted router selection
+//!
+//! Random numbers are drawn uniformly over the whole `u128` range and reduced by rejection sampling,
+//! so that there is no modulo bias whatever the total weight is.
+//! Drawing multiple routers without replacement is implemented by `WeightTree`.
+
+/// A source of uniformly distributed random numbers
+pub trait RandomSource {
+    fn next_u128(&mut self) -> u128;
+}
+
+/// A uniformly distributed number in `[0, bound)`, `bound` must not be `0`
+pub fn uniform_below<R: RandomSource>(rng: &mut R, bound: u128) -> u128 {
+    // `2^128 % bound`, the numbers below it are rejected so that every remainder has the same chance
+    let threshold = bound.wrapping_neg() % bound;
+    loop {
+        let rand_num = rng.next_u128();
+        if rand_num >= threshold {
+            return rand_num % bound;
+        }
+    }
+}
+
+/// The position of the interval containing `value` when `weights` are laid out one after another
+fn locate(weights: &[u128], value: u128) -> usize {
+    let mut high: u128 = 0;
+    for (pos, weight) in weights.iter().enumerate() {
+        high = high.saturating_add(*weight);
+        if value < high {
+            return pos;
+        }
+    }
+
+    weights.len() - 1
+}
+
+/// Draw one index of `weights` with the probability proportional to its weight
+/// Returns `None` if the total weight is `0`
+pub fn weighted_sample<R: RandomSource>(weights: &[u128], rng: &mut R) -> Option<usize> {
+    let total = weights.iter().fold(0u128, |sum, weight| sum.saturating_add(*weight));
+    if total == 0 {
+        return None;
+    }
+
+    Some(locate(weights, uniform_below(rng, total)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic random source for tests
+    struct SplitMix(u64);
+
+    impl SplitMix {
+        fn next_u64(&mut self) -> u64 {
+            self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+            let mut z = self.0;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+            z ^ (z >> 31)
+        }
+    }
+
+    impl RandomSource for SplitMix {
+        fn next_u128(&mut self) -> u128 {
+            ((self.next_u64() as u128) << 64) | (self.next_u64() as u128)
+        }
+    }
+
+    /// Always returns the same number
+    struct Constant(u128);
+
+    impl RandomSource for Constant {
+        fn next_u128(&mut self) -> u128 {
+            self.0
+        }
+    }
+
+    /// Returns the numbers in order
+    struct Sequence(ink::prelude::vec::Vec<u128>);
+
+    impl RandomSource for Sequence {
+        fn next_u128(&mut self) -> u128 {
+            self.0.remove(0)
+        }
+    }
+
+    const SAMPLES: usize = 200_000;
+
+    fn assert_close(observed: usize, expected: f64) {
+        let ratio = observed as f64 / SAMPLES as f64;
+        assert!((ratio - expected).abs() < 0.005, "observed {}, expected {}", ratio, expected);
+    }
+
+    #[test]
+    fn uniform_below_rejects_the_biased_zone() {
+        // the biased zone is `[0, 1)` when the bound is `3`, as `2^128 % 3 == 1`, so `0` is redrawn
+        assert_eq!(uniform_below(&mut Sequence(ink::prelude::vec![0, 5]), 3), 2);
+        assert_eq!(uniform_below(&mut Sequence(ink::prelude::vec![1]), 3), 1);
+        // nothing is rejected when the bound divides `2^128`
+        assert_eq!(uniform_below(&mut Constant(0), 1 << 127), 0);
+        assert_eq!(uniform_below(&mut Constant(u128::MAX), 1 << 127), (1 << 127) - 1);
+    }
+
+    #[test]
+    fn frequencies_match_weights() {
+        let weights = [10u128, 20, 30, 40];
+        let mut rng = SplitMix(7);
+        let mut counts = [0usize; 4];
+        for _ in 0..SAMPLES {
+            counts[weighted_sample(&weights, &mut rng).unwrap()] += 1;
+        }
+
+        for (idx, count) in counts.iter().enumerate() {
+            assert_close(*count, weights[idx] as f64 / 100.0);
+        }
+    }
+
+    #[test]
+    fn weights_above_u16_are_addressed() {
+        let weights = [1u128 << 100, 3u128 << 100, 70_000];
+        let mut rng = SplitMix(11);
+        let mut counts = [0usize; 3];
+        for _ in 0..SAMPLES {
+            counts[weighted_sample(&weights, &mut rng).unwrap()] += 1;
+        }
+
+        assert_close(counts[0], 0.25);
+        assert_close(counts[1], 0.75);
+        assert_eq!(counts[2], 0);
+    }
+
+    #[test]
+    fn saturated_total_weight_does_not_overflow() {
+        // the total weight saturates at `u128::MAX`, and so does the end of the last interval
+        let weights = [u128::MAX - 1, u128::MAX - 1];
+        assert_eq!(weighted_sample(&weights, &mut Constant(u128::MAX - 2)), Some(0));
+        assert_eq!(weighted_sample(&weights, &mut Constant(u128::MAX - 1)), Some(1));
+    }
+
+    #[test]
+    fn zero_weights_are_never_drawn() {
+        let mut rng = SplitMix(17);
+
+        assert_eq!(weighted_sample(&[0u128, 0], &mut rng), None);
+        assert_eq!(weighted_sample(&[], &mut rng), None);
+        for _ in 0..100 {
+            assert_eq!(weighted_sample(&[0u128, 5, 0], &mut rng), Some(1));
+        }
+    }
+}
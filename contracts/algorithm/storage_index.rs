@@ -0,0 +1,249 @@
+//! Bounded index of storage keys
+//!
+//! A `Vec` of keys in the storage root is loaded and written back as a whole on every call,
+//! so its weight grows with the traffic. `KeyIndex` keeps the keys in a doubly linked list on top of `Mapping`,
+//! such that inserting, removing, paging and clearing only touch the nodes they need.
+
+use ink::storage::traits::{AutoKey, Packed, StorageKey};
+use ink::storage::Mapping;
+
+/// Node id `0` means no node
+const NIL: u64 = 0;
+
+/// The max number of items returned or removed in one call
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+#[derive(Debug, Clone, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+pub struct IndexNode<K> {
+    key: K,
+    prev: u64,
+    next: u64,
+}
+
+/// Keys in insertion order, which can be iterated with a cursor
+///
+/// `KEY` is the salt of the storage keys of the fields, which is resolved from the field of the contract storage,
+/// such that every index in the contract has its own nodes
+#[ink::storage_item]
+#[derive(Debug)]
+pub struct KeyIndex<K: Packed + Clone, KEY: StorageKey = AutoKey> {
+    head: u64,
+    tail: u64,
+    next_node: u64,
+    len: u64,
+    nodes: Mapping<u64, IndexNode<K>>,
+    positions: Mapping<K, u64>,
+}
+
+impl<K: Packed + Clone, KEY: StorageKey> Default for KeyIndex<K, KEY> {
+    fn default() -> Self {
+        KeyIndex {
+            head: NIL,
+            tail: NIL,
+            next_node: NIL,
+            len: 0,
+            nodes: Mapping::default(),
+            positions: Mapping::default(),
+        }
+    }
+}
+
+// A general index, not every operation is used by the contract
+#[allow(dead_code)]
+impl<K: Packed + Clone, KEY: StorageKey> KeyIndex<K, KEY> {
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.positions.contains(key)
+    }
+
+    /// Append `key` to the tail, does nothing if it is already indexed
+    pub fn insert(&mut self, key: &K) {
+        if self.positions.contains(key) {
+            return;
+        }
+
+        self.next_node += 1;
+        let node_id = self.next_node;
+
+        if let Some(mut tail) = self.nodes.get(self.tail) {
+            tail.next = node_id;
+            self.nodes.insert(self.tail, &tail);
+        } else {
+            self.head = node_id;
+        }
+
+        self.nodes.insert(node_id, &IndexNode {
+            key: key.clone(),
+            prev: self.tail,
+            next: NIL,
+        });
+        self.positions.insert(key, &node_id);
+        self.tail = node_id;
+        self.len += 1;
+    }
+
+    /// Remove `key` from the index, returns whether it was indexed
+    pub fn remove(&mut self, key: &K) -> bool {
+        if let Some(node_id) = self.positions.get(key) {
+            self.unlink(node_id);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Remove at most `limit` keys from the head, and returns them
+    pub fn pop_front(&mut self, limit: u32) -> ink::prelude::vec::Vec<K> {
+        let mut keys = ink::prelude::vec![];
+        while (keys.len() as u32) < limit.min(MAX_PAGE_SIZE) {
+            if let Some(node) = self.nodes.get(self.head) {
+                self.unlink(self.head);
+                keys.push(node.key);
+            } else {
+                break;
+            }
+        }
+
+        keys
+    }
+
+    /// Returns at most `limit` keys starting from `cursor`, or from the head if `cursor` is `None`,
+    /// together with the cursor of the next page, which is `None` if there are no more keys
+    pub fn page(&self, cursor: Option<u64>, limit: u32) -> (ink::prelude::vec::Vec<K>, Option<u64>) {
+        let mut keys = ink::prelude::vec![];
+        let mut node_id = cursor.unwrap_or(self.head);
+        while (keys.len() as u32) < limit.min(MAX_PAGE_SIZE) {
+            if let Some(node) = self.nodes.get(node_id) {
+                keys.push(node.key);
+                node_id = node.next;
+            } else {
+                node_id = NIL;
+                break;
+            }
+        }
+
+        if node_id == NIL {
+            (keys, None)
+        } else {
+            (keys, Some(node_id))
+        }
+    }
+
+    /// Iterate all the keys from the head
+    pub fn iter(&self) -> KeyIndexIter<'_, K, KEY> {
+        KeyIndexIter {
+            index: self,
+            node_id: self.head,
+        }
+    }
+
+    fn unlink(&mut self, node_id: u64) {
+        if let Some(node) = self.nodes.get(node_id) {
+            if let Some(mut prev) = self.nodes.get(node.prev) {
+                prev.next = node.next;
+                self.nodes.insert(node.prev, &prev);
+            } else {
+                self.head = node.next;
+            }
+
+            if let Some(mut next) = self.nodes.get(node.next) {
+                next.prev = node.prev;
+                self.nodes.insert(node.next, &next);
+            } else {
+                self.tail = node.prev;
+            }
+
+            self.nodes.remove(node_id);
+            self.positions.remove(&node.key);
+            self.len -= 1;
+        }
+    }
+}
+
+pub struct KeyIndexIter<'a, K: Packed + Clone, KEY: StorageKey> {
+    index: &'a KeyIndex<K, KEY>,
+    node_id: u64,
+}
+
+impl<'a, K: Packed + Clone, KEY: StorageKey> Iterator for KeyIndexIter<'a, K, KEY> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        let node = self.index.nodes.get(self.node_id)?;
+        self.node_id = node.next;
+        Some(node.key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index_of(keys: core::ops::Range<u32>) -> KeyIndex<u32> {
+        let mut index = KeyIndex::default();
+        for key in keys {
+            index.insert(&key);
+        }
+
+        index
+    }
+
+    #[ink::test]
+    fn pages_follow_the_cursor() {
+        let mut index = index_of(0..5);
+        // an indexed key is not appended again
+        index.insert(&2);
+        assert_eq!(index.len(), 5);
+
+        let (first, cursor) = index.page(None, 2);
+        assert_eq!(first, ink::prelude::vec![0, 1]);
+        let (second, cursor) = index.page(cursor, 2);
+        assert_eq!(second, ink::prelude::vec![2, 3]);
+        let (last, cursor) = index.page(cursor, 2);
+        assert_eq!(last, ink::prelude::vec![4]);
+        assert_eq!(cursor, None);
+
+        // the keys removed in the middle are skipped, the rest keep their order
+        assert!(index.remove(&2));
+        assert!(!index.remove(&2));
+        index.insert(&2);
+        assert_eq!(index.page(None, 10), (ink::prelude::vec![0, 1, 3, 4, 2], None));
+        assert_eq!(index.iter().collect::<ink::prelude::vec::Vec<u32>>(), ink::prelude::vec![0, 1, 3, 4, 2]);
+    }
+
+    #[ink::test]
+    fn pages_are_bounded() {
+        let index = index_of(0..(MAX_PAGE_SIZE + 5));
+
+        let (keys, cursor) = index.page(None, u32::MAX);
+        assert_eq!(keys.len() as u32, MAX_PAGE_SIZE);
+        assert_eq!(index.page(cursor, u32::MAX).0, (MAX_PAGE_SIZE..(MAX_PAGE_SIZE + 5)).collect::<ink::prelude::vec::Vec<u32>>());
+    }
+
+    #[ink::test]
+    fn pop_front_removes_the_earliest_keys() {
+        let mut index = index_of(0..5);
+
+        assert_eq!(index.pop_front(2), ink::prelude::vec![0, 1]);
+        assert_eq!(index.len(), 3);
+        assert!(!index.contains(&0));
+        assert!(index.contains(&2));
+
+        assert_eq!(index.pop_front(10), ink::prelude::vec![2, 3, 4]);
+        assert!(index.is_empty());
+        assert_eq!(index.page(None, 10), (ink::prelude::vec![], None));
+        assert_eq!(index.pop_front(10), ink::prelude::vec![]);
+
+        // the emptied index can be reused
+        index.insert(&7);
+        assert_eq!(index.page(None, 10), (ink::prelude::vec![7], None));
+    }
+}
@@ -0,0 +1,342 @@
+//! Cumulative router weights
+//!
+//! Building the selection intervals reads every router from storage, and locating a random number in them scans the list.
+//! `WeightTree` keeps the weights in a Fenwick tree on top of `Mapping`, which is updated when a weight changes,
+//! such that updating a weight and drawing a router both take O(log n) storage reads.
+
+use ink::primitives::AccountId;
+use ink::storage::Mapping;
+
+use crate::selection::{uniform_below, RandomSource};
+
+/// The max weight of one slot, such that the sum of the weights of `2^32` slots cannot overflow
+pub const MAX_WEIGHT: u128 = u128::MAX >> 32;
+
+/// The nodes of a Fenwick tree, indexed from `1`.
+/// Node `i` keeps the sum of the weights of the slots in `(i - lowbit(i), i]`
+pub trait FenwickNodes {
+    fn node(&self, idx: u64) -> u128;
+    fn set_node(&mut self, idx: u64, value: u128);
+}
+
+fn lowbit(idx: u64) -> u64 {
+    idx & idx.wrapping_neg()
+}
+
+/// The sum of the weights of the slots in `[1, idx]`
+pub fn prefix<T: FenwickNodes>(nodes: &T, mut idx: u64) -> u128 {
+    let mut sum: u128 = 0;
+    while idx > 0 {
+        sum += nodes.node(idx);
+        idx -= lowbit(idx);
+    }
+
+    sum
+}
+
+/// Append the slot `len + 1` with `weight`, where `len` is the number of the existing slots
+pub fn append<T: FenwickNodes>(nodes: &mut T, len: u64, weight: u128) {
+    let idx = len + 1;
+    let covered = prefix(nodes, len) - prefix(nodes, idx - lowbit(idx));
+    nodes.set_node(idx, covered + weight);
+}
+
+/// Increase the weight of the slot `idx` by `delta`
+pub fn increase<T: FenwickNodes>(nodes: &mut T, len: u64, mut idx: u64, delta: u128) {
+    while idx <= len {
+        let value = nodes.node(idx);
+        nodes.set_node(idx, value + delta);
+        idx += lowbit(idx);
+    }
+}
+
+/// Decrease the weight of the slot `idx` by `delta`
+pub fn decrease<T: FenwickNodes>(nodes: &mut T, len: u64, mut idx: u64, delta: u128) {
+    while idx <= len {
+        let value = nodes.node(idx);
+        nodes.set_node(idx, value - delta);
+        idx += lowbit(idx);
+    }
+}
+
+/// The slot whose interval contains `value`, when the weights are laid out one after another
+/// The slots in `removed` are treated as if their weights were `0`
+pub fn find<T: FenwickNodes>(nodes: &T, len: u64, mut value: u128, removed: &[(u64, u128)]) -> u64 {
+    let mut pos: u64 = 0;
+    let mut step: u64 = if len == 0 { 0 } else { 1 << (63 - len.leading_zeros()) };
+    while step > 0 {
+        let next = pos + step;
+        if next <= len {
+            let removed_weight: u128 = removed.iter()
+                .filter(|ele| (ele.0 > pos) && (ele.0 <= next))
+                .map(|ele| ele.1)
+                .sum();
+            let node = nodes.node(next) - removed_weight;
+
+            if node <= value {
+                pos = next;
+                value -= node;
+            }
+        }
+
+        step >>= 1;
+    }
+
+    pos + 1
+}
+
+/// Draw at most `n` different slots without replacement.
+/// Every draw picks one of the remaining slots with the probability proportional to its weight,
+/// then the weight is removed and the rest are renormalized.
+/// The slots in `removed` and the ones with zero weight are never drawn, a slot listed twice in `removed` is removed once.
+pub fn sample_without_replacement<T: FenwickNodes, R: RandomSource>(nodes: &T, len: u64, n: usize, mut removed: ink::prelude::vec::Vec<(u64, u128)>, rng: &mut R) -> ink::prelude::vec::Vec<u64> {
+    removed.sort_unstable_by_key(|ele| ele.0);
+    removed.dedup_by_key(|ele| ele.0);
+    let removed_weight: u128 = removed.iter().map(|ele| ele.1).sum();
+    let mut total = prefix(nodes, len) - removed_weight;
+
+    let mut selected = ink::prelude::vec![];
+    while (selected.len() < n) && (total > 0) {
+        let slot = find(nodes, len, uniform_below(rng, total), &removed);
+        let weight = prefix(nodes, slot) - prefix(nodes, slot - 1);
+
+        selected.push(slot);
+        removed.push((slot, weight));
+        total -= weight;
+    }
+
+    selected
+}
+
+/// The selection weights of the routers
+#[ink::storage_item]
+#[derive(Debug, Default)]
+pub struct WeightTree {
+    /// The number of slots, a slot is kept for a router once it is assigned
+    len: u64,
+    nodes: Mapping<u64, u128>,
+    weights: Mapping<u64, u128>,
+    slots: Mapping<u64, AccountId>,
+    slot_of: Mapping<AccountId, u64>,
+}
+
+impl FenwickNodes for WeightTree {
+    fn node(&self, idx: u64) -> u128 {
+        self.nodes.get(idx).unwrap_or(0)
+    }
+
+    fn set_node(&mut self, idx: u64, value: u128) {
+        self.nodes.insert(idx, &value);
+    }
+}
+
+impl WeightTree {
+    /// The sum of all the weights
+    #[allow(dead_code)]
+    pub fn total(&self) -> u128 {
+        prefix(self, self.len)
+    }
+
+    pub fn weight_of(&self, id: &AccountId) -> u128 {
+        self.slot_of.get(id).and_then(|slot| self.weights.get(slot)).unwrap_or(0)
+    }
+
+    /// Set the weight of `id`, a slot is assigned to it if it has none.
+    /// The weight is capped at `MAX_WEIGHT`
+    pub fn set(&mut self, id: &AccountId, weight: u128) {
+        let weight = weight.min(MAX_WEIGHT);
+        if let Some(slot) = self.slot_of.get(id) {
+            let old = self.weights.get(slot).unwrap_or(0);
+            if weight > old {
+                increase(self, self.len, slot, weight - old);
+            } else if weight < old {
+                decrease(self, self.len, slot, old - weight);
+            }
+
+            self.weights.insert(slot, &weight);
+        } else {
+            if weight == 0 {
+                return;
+            }
+
+            append(self, self.len, weight);
+            self.len += 1;
+            self.weights.insert(self.len, &weight);
+            self.slots.insert(self.len, id);
+            self.slot_of.insert(id, &self.len);
+        }
+    }
+
+    /// At most `limit` routers and their weights in the slot order, starting from the slot `from`
+    pub fn slots(&self, from: u64, limit: u32) -> ink::prelude::vec::Vec<(AccountId, u128)> {
+        let to = from.max(1).saturating_add(limit.min(crate::storage_index::MAX_PAGE_SIZE) as u64);
+        (from.max(1)..to.min(self.len + 1))
+            .filter_map(|slot| self.slots.get(slot).map(|id| (id, self.weights.get(slot).unwrap_or(0))))
+            .collect()
+    }
+
+    /// Draw at most `n` different routers except `excluded` according to their weights
+    pub fn sample<R: RandomSource>(&self, n: usize, excluded: &[AccountId], rng: &mut R) -> ink::prelude::vec::Vec<AccountId> {
+        let removed = excluded.iter()
+            .filter_map(|id| self.slot_of.get(id))
+            .map(|slot| (slot, self.weights.get(slot).unwrap_or(0)))
+            .collect();
+
+        sample_without_replacement(self, self.len, n, removed, rng)
+            .into_iter()
+            .filter_map(|slot| self.slots.get(slot))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    impl FenwickNodes for ink::prelude::vec::Vec<u128> {
+        fn node(&self, idx: u64) -> u128 {
+            self.get(idx as usize).cloned().unwrap_or(0)
+        }
+
+        fn set_node(&mut self, idx: u64, value: u128) {
+            if self.len() <= idx as usize {
+                self.resize(idx as usize + 1, 0);
+            }
+            self[idx as usize] = value;
+        }
+    }
+
+    /// Deterministic random source for tests
+    struct SplitMix(u64);
+
+    impl SplitMix {
+        fn next_u64(&mut self) -> u64 {
+            self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+            let mut z = self.0;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+            z ^ (z >> 31)
+        }
+    }
+
+    impl RandomSource for SplitMix {
+        fn next_u128(&mut self) -> u128 {
+            ((self.next_u64() as u128) << 64) | (self.next_u64() as u128)
+        }
+    }
+
+    fn build(weights: &[u128]) -> ink::prelude::vec::Vec<u128> {
+        let mut nodes = ink::prelude::vec![];
+        for (idx, weight) in weights.iter().enumerate() {
+            append(&mut nodes, idx as u64, *weight);
+        }
+
+        nodes
+    }
+
+    #[test]
+    fn prefix_sums_follow_updates() {
+        let mut weights: ink::prelude::vec::Vec<u128> = (1..=37).map(|ele| ele * 7 % 11).collect();
+        let mut nodes = build(&weights);
+        let len = weights.len() as u64;
+
+        increase(&mut nodes, len, 5, 100);
+        weights[4] += 100;
+        decrease(&mut nodes, len, 32, weights[31]);
+        weights[31] = 0;
+
+        for idx in 0..=len {
+            assert_eq!(prefix(&nodes, idx), weights[..idx as usize].iter().sum::<u128>());
+        }
+    }
+
+    #[test]
+    fn find_locates_every_interval() {
+        let weights = [3u128, 0, 5, 1, 0, 2];
+        let nodes = build(&weights);
+
+        let located: ink::prelude::vec::Vec<u64> = (0..11).map(|value| find(&nodes, 6, value, &[])).collect();
+        assert_eq!(located, ink::prelude::vec![1, 1, 1, 3, 3, 3, 3, 3, 4, 6, 6]);
+
+        // without slot 3, the intervals after it move forward
+        assert_eq!(find(&nodes, 6, 3, &[(3, 5)]), 4);
+        assert_eq!(find(&nodes, 6, 4, &[(3, 5)]), 6);
+    }
+
+    #[test]
+    fn sampling_matches_renormalized_weights() {
+        const SAMPLES: usize = 100_000;
+
+        let weights = [10u128, 20, 30, 40];
+        let nodes = build(&weights);
+
+        // the probability of `i` being drawn in two draws is
+        // `w_i / W + sum_{j != i}(w_j / W * w_i / (W - w_j))`
+        let expected: ink::prelude::vec::Vec<f64> = (0..4).map(|i| {
+            let w_i = weights[i] as f64;
+            let second: f64 = (0..4).filter(|j| *j != i).map(|j| {
+                let w_j = weights[j] as f64;
+                w_j / 100.0 * w_i / (100.0 - w_j)
+            }).sum();
+
+            w_i / 100.0 + second
+        }).collect();
+
+        let mut rng = SplitMix(23);
+        let mut counts = [0usize; 4];
+        for _ in 0..SAMPLES {
+            let selected = sample_without_replacement(&nodes, 4, 2, ink::prelude::vec![], &mut rng);
+            assert_eq!(selected.len(), 2);
+            assert_ne!(selected[0], selected[1]);
+
+            for slot in selected {
+                counts[slot as usize - 1] += 1;
+            }
+        }
+
+        for (idx, count) in counts.iter().enumerate() {
+            let ratio = *count as f64 / SAMPLES as f64;
+            assert!((ratio - expected[idx]).abs() < 0.005, "observed {}, expected {}", ratio, expected[idx]);
+        }
+    }
+
+    #[test]
+    fn removed_slots_are_never_drawn() {
+        let nodes = build(&[0u128, 5, 7]);
+        let mut rng = SplitMix(29);
+
+        for _ in 0..100 {
+            assert_eq!(sample_without_replacement(&nodes, 3, 3, ink::prelude::vec![(3, 7)], &mut rng), ink::prelude::vec![2]);
+        }
+    }
+
+    #[ink::test]
+    fn weights_are_capped() {
+        let mut tree = WeightTree::default();
+        for id in 0..4u8 {
+            tree.set(&AccountId::from([id; 32]), u128::MAX);
+        }
+
+        assert_eq!(tree.weight_of(&AccountId::from([0; 32])), MAX_WEIGHT);
+        assert_eq!(tree.total(), MAX_WEIGHT * 4);
+
+        let mut rng = SplitMix(31);
+        assert_eq!(tree.sample(4, &[], &mut rng).len(), 4);
+    }
+
+    #[ink::test]
+    fn duplicate_excluded_ids_are_excluded_once() {
+        let mut tree = WeightTree::default();
+        for id in 1..=3u8 {
+            tree.set(&AccountId::from([id; 32]), id as u128 * 10);
+        }
+
+        let excluded = AccountId::from([3; 32]);
+        let mut rng = SplitMix(37);
+        for _ in 0..100 {
+            let mut selected = tree.sample(3, &[excluded, excluded], &mut rng);
+            selected.sort();
+            assert_eq!(selected, ink::prelude::vec![AccountId::from([1; 32]), AccountId::from([2; 32])]);
+        }
+    }
+}
@@ -1,9 +1,9 @@
-#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
 
-mod test;
+pub mod test;
 
 use payload::message_protocol::{ MessagePayload, MessageItem, MsgDetail};
-use payload::message_define::{ISentMessage, IReceivedMessage};
+use payload::message_define::IReceivedMessage;
 
 #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
 // #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
@@ -77,7 +77,7 @@ mod callee {
     impl Callee {
         /// Constructor that initializes the `bool` value to the given `init_value`.
         #[ink(constructor)]
-        pub fn new(init_value: bool) -> Self {
+        pub fn new(_init_value: bool) -> Self {
             Self { 
                 message: 0,
             }
@@ -112,7 +112,7 @@ mod callee {
         #[ink(message)]
         pub fn get_struct_message_vec_u8(& self, msg: MessageDetail) -> ink::prelude::vec::Vec::<u8>{
             let mut v = ink::prelude::vec::Vec::<u8>::new();
-            let mut v_vec = ink::prelude::vec![msg.clone(), msg.clone()];
+            let v_vec = ink::prelude::vec![msg.clone(), msg.clone()];
             scale::Encode::encode_to(&v_vec, &mut v);
             v
         }
@@ -156,8 +156,7 @@ mod callee {
 
             ink::env::call::build_call::<ink::env::DefaultEnvironment>()
                 .call_type(
-                    ink::env::call::Call::new()
-                        .callee(addr1)
+                    ink::env::call::Call::new(addr1)
                         .gas_limit(0)
                         .transferred_value(0))
                 .exec_input(
@@ -168,8 +167,7 @@ mod callee {
                 )
                 .call_flags(ink::env::CallFlags::default().set_allow_reentry(true))
                 .returns::<()>()
-                .fire().
-                unwrap();
+                .invoke();
 
             self.load();
         }
@@ -180,8 +178,7 @@ mod callee {
 
             ink::env::call::build_call::<ink::env::DefaultEnvironment>()
                 .call_type(
-                    ink::env::call::Call::new()
-                        .callee(addr)
+                    ink::env::call::Call::new(addr)
                         .gas_limit(0)
                         .transferred_value(0))
                 .exec_input(
@@ -191,8 +188,7 @@ mod callee {
                 )
                 .call_flags(ink::env::CallFlags::default().set_allow_reentry(true))
                 .returns::<()>()
-                .fire().
-                unwrap();
+                .invoke();
 
             // self.load();
         }
@@ -206,7 +202,7 @@ mod callee {
         }
 
         #[ink(message)]
-        pub fn get_message(& self, flag: bool) -> u32 {
+        pub fn get_message(& self, _flag: bool) -> u32 {
             self.message
         }
 
@@ -239,9 +235,6 @@ mod callee {
     /// The below code is technically just normal Rust code.
     #[cfg(test)]
     mod tests {
-        /// Imports all the definitions from the outer scope so we can use them here.
-        use super::*;
-
         /// test `Payload`
         #[ink::test]
         fn test_payload() {
@@ -262,7 +255,7 @@ mod callee {
 
             pl.push_item(ink::prelude::string::String::from("1"), super::super::MsgDetail::InkU16(24));
             msg_item.tv = super::super::MsgDetail::InkU8(255);
-            assert_eq!(pl.push_item(ink::prelude::string::String::from("1"), super::super::MsgDetail::InkU16(v_u16)), false);
+            assert!(!pl.push_item(ink::prelude::string::String::from("1"), super::super::MsgDetail::InkU16(v_u16)));
 
             // Attention, `assert_eq` use the concrete implementation of `PartialEq` to chack equal
             // So it doesn't matter whether the `t` and `v` is the same
@@ -270,13 +263,14 @@ mod callee {
         }
 
         /// test `MessageItem::from`, `MessageItem::into` 
+        #[ink::test]
         fn test_from_into(){
-            let mut msg_item = super::super::MessageItem::from(ink::prelude::string::String::from("Nika"), 
+            let msg_item = super::super::MessageItem::from(ink::prelude::string::String::from("Nika"), 
                                                             super::super::MsgDetail::InkU32(128));
 
             let num: u32 = msg_item.in_to::<u32>().unwrap();
 
-            assert_eq!(num, 128 as u32);
+            assert_eq!(num, 128_u32);
         }
     }
 }
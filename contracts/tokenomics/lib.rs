@@ -1,11 +1,9 @@
-#![cfg_attr(not(feature = "std"), no_std)]
-
-use ink_lang as ink;
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
 
 #[ink::contract]
 mod tokenomics {
 
-    use ink_storage::traits::{PackedLayout, SpreadLayout, StorageLayout, SpreadAllocate};
+    use ink::storage::Mapping;
 
     #[derive(Debug, PartialEq, Clone, Eq, scale::Encode, scale::Decode)]
     #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
@@ -14,19 +12,126 @@ mod tokenomics {
         NotEnough,
         ParamInvalid,
         CallerInvalid,
+        /// The native tokens could not be transferred, nothing has changed
+        TransferFailed,
+        /// The router has too many pending withdrawals, claim the unlocked ones first
+        TooManyUnbonding,
+        /// No pending withdrawal has been unlocked
+        NothingToClaim,
+        /// The current epoch has not reached its end block
+        EpochNotEnded,
+        /// The current epoch has recorded the deliveries of `MAX_EPOCH_ROUTERS` routers
+        EpochFull,
     }
 
     /// for test
-    #[derive(Debug, PartialEq, Clone, Eq, SpreadAllocate, PackedLayout, SpreadLayout, scale::Encode, scale::Decode)]
-    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    #[derive(Debug, PartialEq, Clone, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
     pub struct StakingInfo{
         amount: u128,
         reward: u128,
     }
 
+    /// A pending withdrawal, which can be claimed by `claim_unbonded` from the block `unlock_at`
+    #[derive(Debug, PartialEq, Clone, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct UnbondingChunk {
+        amount: u128,
+        unlock_at: BlockNumber,
+    }
+
+    /// The max number of pending withdrawals of one router
+    pub const MAX_UNBONDING_CHUNKS: usize = 16;
+
+    /// The base of the ratios in `SlashAmount::Fraction` and `SlashParams`, i.e. `10000` is 100%
+    pub const RATIO_BASE: u32 = 10000;
+
+    /// How much is slashed from a router
+    #[derive(Debug, PartialEq, Clone, Copy, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    pub enum SlashAmount {
+        /// A fraction of the stake and pending withdrawals, in `RATIO_BASE`
+        Fraction(u32),
+        /// A fixed amount, capped at the stake and pending withdrawals
+        Amount(u128),
+    }
+
+    /// How the slashed funds are split, in `RATIO_BASE`. What is left after the treasury and the reporter is locked in the contract
+    #[derive(Debug, PartialEq, Clone, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct SlashParams {
+        /// `None` locks the share of the treasury
+        treasury: Option<AccountId>,
+        treasury_ratio: u32,
+        reporter_ratio: u32,
+    }
+
+    /// The max number of routers whose deliveries are recorded in one epoch, which bounds the work of `close_epoch`
+    pub const MAX_EPOCH_ROUTERS: u32 = 256;
+
+    /// The staking weight added to this base scales the score of a router in the epoch distribution,
+    /// such that a router without stake still gets a share
+    pub const STAKE_WEIGHT_BASE: u128 = 100;
+
+    /// The verified deliveries of a router recorded in the current epoch
+    #[derive(Debug, Default, PartialEq, Clone, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct EpochWork {
+        deliveries: u32,
+        /// The sum of the credibility of the router at each delivery
+        cred_sum: u128,
+    }
+
+    /// The reward of a router in a closed epoch
+    #[derive(Debug, PartialEq, Clone, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct EpochReward {
+        work: EpochWork,
+        staking_weight: u128,
+        score: u128,
+        reward: u128,
+    }
+
+    /// The record of a closed epoch, the rewards of the routers are kept apart by `get_epoch_reward`
+    #[derive(Debug, PartialEq, Clone, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
+    pub struct EpochSnapshot {
+        start: BlockNumber,
+        end: BlockNumber,
+        /// The emission taken from the reward pool, which can be less than `emission` if the pool runs low
+        emission: u128,
+        /// The part of the emission given to the routers, the rounding remainder is left in the pool
+        distributed: u128,
+        total_score: u128,
+        /// The number of routers rewarded, which are listed by `get_epoch_routers`
+        routers: u32,
+    }
+
+    /// Emitted when an epoch is closed and its emission is distributed
+    #[ink(event)]
+    pub struct EpochClosed {
+        #[ink(topic)]
+        epoch: u32,
+        distributed: u128,
+        routers: u32,
+    }
+
+    /// Emitted when a router is slashed, `reason` is a code defined by the protocol stack contract
+    #[ink(event)]
+    pub struct Slashed {
+        #[ink(topic)]
+        router: AccountId,
+        #[ink(topic)]
+        reason: u32,
+        amount: u128,
+        to_treasury: u128,
+        to_reporter: u128,
+        locked: u128,
+    }
+
     /// system parameters
-    #[derive(Debug, SpreadLayout, SpreadAllocate, scale::Encode, scale::Decode)]
-    #[cfg_attr(feature = "std", derive(StorageLayout, ::scale_info::TypeInfo))]
+    #[derive(Debug, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout))]
     pub struct SysParams {
         gc: u128,
         m: u128,
@@ -38,45 +143,81 @@ mod tokenomics {
     /// Add new fields to the below struct in order
     /// to add new static storage fields to your contract.
     #[ink(storage)]
-    #[derive(SpreadAllocate)]
     pub struct Tokenomics {
         owner: AccountId,
         ps_contract: Option<AccountId>,
-        staking_routers: ink_storage::Mapping<AccountId, StakingInfo>,
+        staking_routers: Mapping<AccountId, StakingInfo>,
         total: u128,
         sp: SysParams,
+        /// The pending withdrawals of the routers, which are not counted in the staking weights but can still be slashed
+        unbonding: Mapping<AccountId, ink::prelude::vec::Vec<UnbondingChunk>>,
+        total_unbonding: u128,
+        /// The number of blocks a withdrawal waits before it can be claimed
+        unbonding_period: BlockNumber,
+        slash_params: SlashParams,
+        /// The slashed funds locked in the contract, which are never paid out
+        total_locked: u128,
+        /// The funds held for the rewards which have not been given to any router yet
+        reward_pool: u128,
+        /// The rewards given to the routers which have not been claimed yet
+        total_rewards: u128,
+        /// The index of the current epoch and the block it starts from
+        epoch: u32,
+        epoch_start: BlockNumber,
+        /// The min number of blocks of an epoch, and the emission distributed when it is closed
+        epoch_length: BlockNumber,
+        epoch_emission: u128,
+        /// The routers which have delivered messages in each epoch, keyed by the epoch and their position,
+        /// and the number of them in the current epoch
+        epoch_routers: Mapping<(u32, u32), AccountId>,
+        epoch_router_count: u32,
+        epoch_work: Mapping<AccountId, EpochWork>,
+        epoch_rewards: Mapping<(u32, AccountId), EpochReward>,
+        epoch_snapshots: Mapping<u32, EpochSnapshot>,
     }
 
     impl Tokenomics {
         /// Constructor that initializes the `bool` value to the given `init_value`.
         #[ink(constructor)]
         pub fn new() -> Self {
-            ink_lang::utils::initialize_contract(|contract: &mut Self| {
-                contract.owner = ink_env::caller::<ink_env::DefaultEnvironment>();
-                contract.ps_contract = None;
-                contract.total = 0;
-                contract.sp = SysParams {
+            Self {
+                owner: Self::env().caller(),
+                ps_contract: None,
+                staking_routers: Mapping::default(),
+                total: 0,
+                sp: SysParams {
                     gc: 100,
                     m: 1000,
                     b: 50,
                     r: 100,
-                }
-            })
+                },
+                unbonding: Mapping::default(),
+                total_unbonding: 0,
+                unbonding_period: 100,
+                slash_params: SlashParams {
+                    treasury: None,
+                    treasury_ratio: 0,
+                    reporter_ratio: 0,
+                },
+                total_locked: 0,
+                reward_pool: 0,
+                total_rewards: 0,
+                epoch: 0,
+                epoch_start: Self::env().block_number(),
+                epoch_length: 1000,
+                epoch_emission: 1000,
+                epoch_routers: Mapping::default(),
+                epoch_router_count: 0,
+                epoch_work: Mapping::default(),
+                epoch_rewards: Mapping::default(),
+                epoch_snapshots: Mapping::default(),
+            }
         }
 
-        /// Constructor that initializes the `bool` value to `false`.
-        ///
-        /// Constructors can delegate to other constructors.
-        // #[ink(constructor)]
-        // pub fn default() -> Self {
-        //     ink_lang::utils::initialize_contract(|_| {})
-        //     // Self::new(Default::default())
-        // }
-
         /// set the protocol stack contract address
         #[ink(message)]
         pub fn set_protocol_stack(&mut self, ps_addr: AccountId) -> Result<(), StakingError>{
-            if ink_env::caller::<ink_env::DefaultEnvironment>() != self.owner {
+            if self.env().caller() != self.owner {
                 // TODO: `chain-extension`
                 return Err(StakingError::CallerInvalid);
             }
@@ -88,7 +229,7 @@ mod tokenomics {
         /// set system parameters
         #[ink(message)]
         pub fn set_sys_params(&mut self, sp: SysParams) -> Result<(), StakingError> {
-            if ink_env::caller::<ink_env::DefaultEnvironment>() != self.owner {
+            if self.env().caller() != self.owner {
                 // TODO: `chain-extension`
                 return Err(StakingError::CallerInvalid);
             }
@@ -101,10 +242,51 @@ mod tokenomics {
             Ok(())
         }
 
+        /// set the number of blocks a withdrawal waits before it can be claimed
+        #[ink(message)]
+        pub fn set_unbonding_period(&mut self, period: BlockNumber) -> Result<(), StakingError> {
+            if self.env().caller() != self.owner {
+                return Err(StakingError::CallerInvalid);
+            }
+
+            self.unbonding_period = period;
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn get_unbonding_period(&self) -> BlockNumber {
+            self.unbonding_period
+        }
+
+        /// set how the slashed funds are split
+        #[ink(message)]
+        pub fn set_slash_params(&mut self, params: SlashParams) -> Result<(), StakingError> {
+            if self.env().caller() != self.owner {
+                return Err(StakingError::CallerInvalid);
+            }
+
+            if params.treasury_ratio.saturating_add(params.reporter_ratio) > RATIO_BASE {
+                return Err(StakingError::ParamInvalid);
+            }
+
+            self.slash_params = params;
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn get_slash_params(&self) -> SlashParams {
+            self.slash_params.clone()
+        }
+
         /// Register router
+        /// Does nothing if the router has been registered, such that its stake is kept
         #[ink(message)]
         pub fn register_router(&mut self) {
-            let router_addr = ink_env::caller::<ink_env::DefaultEnvironment>();
+            let router_addr = self.env().caller();
+            if self.staking_routers.contains(router_addr) {
+                return;
+            }
+
             // register router to storage
             let staking_info = StakingInfo {
                 amount: 0,
@@ -115,53 +297,223 @@ mod tokenomics {
         }
 
         /// Pledge
-        #[ink(message)]
-        pub fn pledge(&mut self, value: u128) {
-            let router_addr = ink_env::caller::<ink_env::DefaultEnvironment>();
-            // TODO: call `transferFrom` to check if `value` is valid
+        /// The native tokens transferred with the call are added to the stake of the caller,
+        /// who is registered as a router if not yet
+        #[ink(message, payable)]
+        pub fn pledge(&mut self) -> Result<(), StakingError> {
+            let router_addr = self.env().caller();
+            let value = self.env().transferred_value();
+            if value == 0 {
+                return Err(StakingError::ParamInvalid);
+            }
 
             // add `value` to the staking amount of the related router
-            if let Some(mut staking_info) = self.staking_routers.get(router_addr) {
-                staking_info.amount += value;
-                self.staking_routers.insert(router_addr, &staking_info);
-                self.total += value;
-            } else{
-                let staking_info = StakingInfo{
-                    amount: value,
-                    reward: 0,
-                };
-                self.staking_routers.insert(router_addr, &staking_info);
-                self.total += value;
-            }
+            let mut staking_info = self.staking_routers.get(router_addr).unwrap_or(StakingInfo {
+                amount: 0,
+                reward: 0,
+            });
+            staking_info.amount = staking_info.amount.checked_add(value).ok_or(StakingError::ParamInvalid)?;
+            self.staking_routers.insert(router_addr, &staking_info);
+            self.total = self.total.saturating_add(value);
+
+            Ok(())
         }
 
         /// withdraw
+        /// `value` of the stake is moved into a pending withdrawal, which stops counting in the staking weights
+        /// and can be claimed by `claim_unbonded` after `unbonding_period` blocks.
+        /// Until then it can still be slashed.
         #[ink(message)]
         pub fn withdraw(&mut self, value: u128) -> Result<(), StakingError> {
-            let router_addr = ink_env::caller::<ink_env::DefaultEnvironment>();
+            let router_addr = self.env().caller();
 
-            if let Some(mut staking_info) = self.staking_routers.get(router_addr) {
-                if staking_info.amount < value {
-                    Err(StakingError::NotEnough)
-                } else {
-                    staking_info.amount -= value;
-                    self.staking_routers.insert(router_addr, &staking_info);
-                    self.total -= value;
-                    Ok(())
+            let mut staking_info = self.staking_routers.get(router_addr).ok_or(StakingError::NotExist)?;
+            if value == 0 {
+                return Err(StakingError::ParamInvalid);
+            }
+            if staking_info.amount < value {
+                return Err(StakingError::NotEnough);
+            }
+
+            let mut chunks = self.unbonding.get(router_addr).unwrap_or_default();
+            if chunks.len() >= MAX_UNBONDING_CHUNKS {
+                return Err(StakingError::TooManyUnbonding);
+            }
+
+            chunks.push(UnbondingChunk {
+                amount: value,
+                unlock_at: self.env().block_number().saturating_add(self.unbonding_period),
+            });
+            self.unbonding.insert(router_addr, &chunks);
+            self.total_unbonding = self.total_unbonding.saturating_add(value);
+
+            staking_info.amount -= value;
+            self.staking_routers.insert(router_addr, &staking_info);
+            self.total = self.total.saturating_sub(value);
+            Ok(())
+        }
+
+        /// Send the pending withdrawals of the caller which have been unlocked back to it, returns the amount sent.
+        /// If the transfer fails, `TransferFailed` is returned and the withdrawals are kept
+        #[ink(message)]
+        pub fn claim_unbonded(&mut self) -> Result<u128, StakingError> {
+            let router_addr = self.env().caller();
+            let now = self.env().block_number();
+
+            let (unlocked, pending): (ink::prelude::vec::Vec<UnbondingChunk>, ink::prelude::vec::Vec<UnbondingChunk>) = self.unbonding
+                .get(router_addr)
+                .unwrap_or_default()
+                .into_iter()
+                .partition(|chunk| chunk.unlock_at <= now);
+
+            let value = unlocked.iter().fold(0u128, |sum, chunk| sum.saturating_add(chunk.amount));
+            if value == 0 {
+                return Err(StakingError::NothingToClaim);
+            }
+
+            // nothing is changed before the transfer succeeds
+            self.transfer_to(router_addr, value)?;
+
+            if pending.is_empty() {
+                self.unbonding.remove(router_addr);
+            } else {
+                self.unbonding.insert(router_addr, &pending);
+            }
+            self.total_unbonding = self.total_unbonding.saturating_sub(value);
+
+            Ok(value)
+        }
+
+        /// get the pending withdrawals of the router
+        #[ink(message)]
+        pub fn get_unbonding(&self, router_addr: AccountId) -> ink::prelude::vec::Vec<UnbondingChunk> {
+            self.unbonding.get(router_addr).unwrap_or_default()
+        }
+
+        /// The native tokens the contract can send, the contract account must keep the existential deposit and the locked slashed funds
+        fn spendable(&self) -> Balance {
+            self.env().balance()
+                .saturating_sub(self.env().minimum_balance())
+                .saturating_sub(self.total_locked)
+        }
+
+        /// Transfer `value` of the native tokens held by the contract to `to`
+        fn transfer_to(&self, to: AccountId, value: Balance) -> Result<(), StakingError> {
+            if value > self.spendable() {
+                return Err(StakingError::TransferFailed);
+            }
+
+            self.env().transfer(to, value).map_err(|_| StakingError::TransferFailed)
+        }
+
+        /// Slash
+        /// Takes `amount` from the stake of the router first, then from its pending withdrawals starting with the latest one.
+        /// The slashed funds are split by `slash_params` between the treasury, the `reporter` and the locked funds.
+        /// Returns the amount slashed, which is less than asked if the router does not have enough.
+        /// If the transfers fail, `TransferFailed` is returned and nothing is slashed
+        #[ink(message)]
+        pub fn slash(&mut self, router_addr: AccountId, amount: SlashAmount, reason: u32, reporter: Option<AccountId>) -> Result<u128, StakingError> {
+            self.ensure_protocol_stack()?;
+
+            let mut staking_info = self.staking_routers.get(router_addr).ok_or(StakingError::NotExist)?;
+            let mut chunks = self.unbonding.get(router_addr).unwrap_or_default();
+            let unbonding = chunks.iter().fold(0u128, |sum, chunk| sum.saturating_add(chunk.amount));
+            let available = staking_info.amount.saturating_add(unbonding);
+
+            let value = match amount {
+                SlashAmount::Fraction(ratio) => {
+                    if ratio > RATIO_BASE {
+                        return Err(StakingError::ParamInvalid);
+                    }
+                    Self::ratio_of(available, ratio)
+                }
+                SlashAmount::Amount(value) => value.min(available),
+            };
+            if value == 0 {
+                return Ok(0);
+            }
+
+            // the ratios add up to at most `RATIO_BASE`, so the shares never exceed `value`
+            let treasury = self.slash_params.treasury.filter(|_| self.slash_params.treasury_ratio > 0);
+            let reporter = reporter.filter(|_| self.slash_params.reporter_ratio > 0);
+            let to_treasury = treasury.map_or(0, |_| Self::ratio_of(value, self.slash_params.treasury_ratio));
+            let to_reporter = reporter.map_or(0, |_| Self::ratio_of(value, self.slash_params.reporter_ratio));
+
+            // nothing is changed before the transfers succeed
+            if to_treasury.saturating_add(to_reporter) > self.spendable() {
+                return Err(StakingError::TransferFailed);
+            }
+            if let Some(treasury) = treasury {
+                self.transfer_to(treasury, to_treasury)?;
+            }
+            if let Some(reporter) = reporter {
+                self.transfer_to(reporter, to_reporter)?;
+            }
+            let locked = value - to_treasury - to_reporter;
+
+            let from_stake = value.min(staking_info.amount);
+            staking_info.amount -= from_stake;
+            self.staking_routers.insert(router_addr, &staking_info);
+            self.total = self.total.saturating_sub(from_stake);
+
+            let mut rest = value - from_stake;
+            self.total_unbonding = self.total_unbonding.saturating_sub(rest);
+            while rest > 0 {
+                let Some(chunk) = chunks.last_mut() else {
+                    break;
+                };
+                let taken = rest.min(chunk.amount);
+                chunk.amount -= taken;
+                rest -= taken;
+                if chunk.amount == 0 {
+                    chunks.pop();
                 }
+            }
+            if chunks.is_empty() {
+                self.unbonding.remove(router_addr);
             } else {
-                Err(StakingError::NotExist)
+                self.unbonding.insert(router_addr, &chunks);
             }
+            self.total_locked = self.total_locked.saturating_add(locked);
+
+            self.env().emit_event(Slashed {
+                router: router_addr,
+                reason,
+                amount: value,
+                to_treasury,
+                to_reporter,
+                locked,
+            });
+
+            Ok(value)
+        }
+
+        /// get the total amount of the slashed funds locked in the contract
+        /// The runtime has no burn address, so the funds stay in the balance of the contract, but they are never sent out
+        #[ink(message)]
+        pub fn get_total_locked(&self) -> u128 {
+            self.total_locked
+        }
+
+        /// `value * ratio / RATIO_BASE`
+        fn ratio_of(value: u128, ratio: u32) -> u128 {
+            value.saturating_mul(ratio as u128) / RATIO_BASE as u128
+        }
+
+        /// Only the protocol stack contract is allowed, `CallerInvalid` if it is not set
+        fn ensure_protocol_stack(&self) -> Result<(), StakingError> {
+            if self.ps_contract != Some(self.env().caller()) {
+                // TODO: `chain-extension`
+                return Err(StakingError::CallerInvalid);
+            }
+
+            Ok(())
         }
 
         // get the staking amount of the router
         #[ink(message)]
         pub fn get_staking_info(&self, router_addr: AccountId) -> Option<StakingInfo> {
-            if let Some(staking_info) = self.staking_routers.get(router_addr) {
-                Some(staking_info)
-            } else{
-                None
-            }
+            self.staking_routers.get(router_addr)
         }
 
         /// get staking score
@@ -185,25 +537,28 @@ mod tokenomics {
         }
 
         /// Reward
+        /// The reward is taken from the reward pool, and is capped at what is left in the pool.
+        /// It is given per call, the epoch distribution by `record_delivery` and `close_epoch` rewards the routers by their work instead
         #[ink(message)]
         pub fn reward(&mut self, router_addr: AccountId, credibility: u32) -> Result<(), StakingError>{
-            if ink_env::caller::<ink_env::DefaultEnvironment>() != self.ps_contract.unwrap() {
-                // TODO: `chain-extension`
-                return Err(StakingError::CallerInvalid);
-            }
+            self.ensure_protocol_stack()?;
 
             if credibility > 100 {
                 return Err(StakingError::ParamInvalid);
             }
             
             if let Some(mut staking_info) = self.staking_routers.get(router_addr) {
-                if credibility <= 50 {
-                    staking_info.reward += self.sp.r;
-                    self.staking_routers.insert(router_addr, &staking_info);
+                let reward = if credibility <= 50 {
+                    self.sp.r
                 } else {
-                    staking_info.reward += self.sp.r + self.sp.r * (credibility as u128 - 50) / 50;
-                    self.staking_routers.insert(router_addr, &staking_info);
-                }
+                    self.sp.r.saturating_add(self.sp.r.saturating_mul(credibility as u128 - 50) / 50)
+                };
+
+                let reward = reward.min(self.reward_pool);
+                staking_info.reward = staking_info.reward.saturating_add(reward);
+                self.staking_routers.insert(router_addr, &staking_info);
+                self.reward_pool -= reward;
+                self.total_rewards = self.total_rewards.saturating_add(reward);
                 
                 Ok(())
             }else {
@@ -211,6 +566,218 @@ mod tokenomics {
             }
         }
 
+        /// Top up the reward pool with the native tokens transferred with the call
+        #[ink(message, payable)]
+        pub fn fund_reward_pool(&mut self) -> Result<(), StakingError> {
+            let value = self.env().transferred_value();
+            if value == 0 {
+                return Err(StakingError::ParamInvalid);
+            }
+
+            self.reward_pool = self.reward_pool.checked_add(value).ok_or(StakingError::ParamInvalid)?;
+            Ok(())
+        }
+
+        /// Claim the rewards of the caller and reset them, returns the amount claimed.
+        /// With `compound` the rewards are added to the stake, otherwise they are sent to the caller.
+        /// If the transfer fails, `TransferFailed` is returned and the rewards are kept
+        #[ink(message)]
+        pub fn claim_rewards(&mut self, compound: bool) -> Result<u128, StakingError> {
+            let router_addr = self.env().caller();
+
+            let mut staking_info = self.staking_routers.get(router_addr).ok_or(StakingError::NotExist)?;
+            let value = staking_info.reward;
+            if value == 0 {
+                return Err(StakingError::NothingToClaim);
+            }
+
+            if compound {
+                staking_info.amount = staking_info.amount.checked_add(value).ok_or(StakingError::ParamInvalid)?;
+                self.total = self.total.saturating_add(value);
+            } else {
+                // nothing is changed before the transfer succeeds
+                self.transfer_to(router_addr, value)?;
+            }
+
+            staking_info.reward = 0;
+            self.staking_routers.insert(router_addr, &staking_info);
+            self.total_rewards = self.total_rewards.saturating_sub(value);
+
+            Ok(value)
+        }
+
+        /// get the funds left in the reward pool, and the total rewards given to the routers but not claimed yet
+        #[ink(message)]
+        pub fn get_reward_pool(&self) -> (u128, u128) {
+            (self.reward_pool, self.total_rewards)
+        }
+
+        /// set the min number of blocks of an epoch and the emission distributed when it is closed
+        /// The current epoch is affected as well
+        #[ink(message)]
+        pub fn set_epoch_params(&mut self, length: BlockNumber, emission: u128) -> Result<(), StakingError> {
+            if self.env().caller() != self.owner {
+                return Err(StakingError::CallerInvalid);
+            }
+
+            if length == 0 {
+                return Err(StakingError::ParamInvalid);
+            }
+
+            self.epoch_length = length;
+            self.epoch_emission = emission;
+            Ok(())
+        }
+
+        /// get the min number of blocks of an epoch and the emission distributed when it is closed
+        #[ink(message)]
+        pub fn get_epoch_params(&self) -> (BlockNumber, u128) {
+            (self.epoch_length, self.epoch_emission)
+        }
+
+        /// Record a verified delivery of the router with its current `credibility` in the current epoch
+        #[ink(message)]
+        pub fn record_delivery(&mut self, router_addr: AccountId, credibility: u32) -> Result<(), StakingError> {
+            self.ensure_protocol_stack()?;
+
+            if credibility > 100 {
+                return Err(StakingError::ParamInvalid);
+            }
+
+            if !self.staking_routers.contains(router_addr) {
+                return Err(StakingError::NotExist);
+            }
+
+            let mut work = match self.epoch_work.get(router_addr) {
+                Some(work) => work,
+                None => {
+                    if self.epoch_router_count >= MAX_EPOCH_ROUTERS {
+                        return Err(StakingError::EpochFull);
+                    }
+                    self.epoch_routers.insert((self.epoch, self.epoch_router_count), &router_addr);
+                    self.epoch_router_count += 1;
+                    EpochWork::default()
+                }
+            };
+            work.deliveries = work.deliveries.saturating_add(1);
+            work.cred_sum = work.cred_sum.saturating_add(credibility as u128);
+            self.epoch_work.insert(router_addr, &work);
+
+            Ok(())
+        }
+
+        /// Close the current epoch once it has lasted `epoch_length` blocks, anyone can call it.
+        /// The emission is taken from the reward pool and split between the routers by their scores,
+        /// which are the sums of their credibility at each delivery, scaled by `STAKE_WEIGHT_BASE` plus their staking weights.
+        /// A snapshot is kept for `get_epoch_snapshot` and the reward of each router for `get_epoch_reward`,
+        /// and the index of the closed epoch is returned
+        #[ink(message)]
+        pub fn close_epoch(&mut self) -> Result<u32, StakingError> {
+            let now = self.env().block_number();
+            if now < self.epoch_start.saturating_add(self.epoch_length) {
+                return Err(StakingError::EpochNotEnded);
+            }
+
+            let epoch = self.epoch;
+            let mut rewards = ink::prelude::vec::Vec::with_capacity(self.epoch_router_count as usize);
+            let mut total_score: u128 = 0;
+            for position in 0..self.epoch_router_count {
+                let Some(router) = self.epoch_routers.get((epoch, position)) else {
+                    continue;
+                };
+                let work = self.epoch_work.take(router).unwrap_or_default();
+                let staking_weight = self.get_staking_weights(router).unwrap_or(0);
+                let score = work.cred_sum.saturating_mul(STAKE_WEIGHT_BASE.saturating_add(staking_weight));
+                total_score = total_score.saturating_add(score);
+                rewards.push((router, EpochReward {
+                    work,
+                    staking_weight,
+                    score,
+                    reward: 0,
+                }));
+            }
+
+            let emission = if total_score > 0 {
+                self.epoch_emission.min(self.reward_pool)
+            } else {
+                0
+            };
+            let mut distributed: u128 = 0;
+            for (router, mut item) in rewards {
+                item.reward = emission.saturating_mul(item.score).checked_div(total_score).unwrap_or(0);
+                if item.reward > 0 {
+                    if let Some(mut staking_info) = self.staking_routers.get(router) {
+                        staking_info.reward = staking_info.reward.saturating_add(item.reward);
+                        self.staking_routers.insert(router, &staking_info);
+                        distributed = distributed.saturating_add(item.reward);
+                    }
+                }
+
+                self.epoch_rewards.insert((epoch, router), &item);
+            }
+            self.reward_pool = self.reward_pool.saturating_sub(distributed);
+            self.total_rewards = self.total_rewards.saturating_add(distributed);
+
+            let routers = self.epoch_router_count;
+            self.epoch_snapshots.insert(epoch, &EpochSnapshot {
+                start: self.epoch_start,
+                end: now,
+                emission,
+                distributed,
+                total_score,
+                routers,
+            });
+            self.epoch = epoch.saturating_add(1);
+            self.epoch_start = now;
+            self.epoch_router_count = 0;
+
+            self.env().emit_event(EpochClosed {
+                epoch,
+                distributed,
+                routers,
+            });
+
+            Ok(epoch)
+        }
+
+        /// get the index of the current epoch and the block it starts from
+        #[ink(message)]
+        pub fn get_current_epoch(&self) -> (u32, BlockNumber) {
+            (self.epoch, self.epoch_start)
+        }
+
+        /// get the deliveries of the router recorded in the current epoch
+        #[ink(message)]
+        pub fn get_epoch_work(&self, router_addr: AccountId) -> Option<EpochWork> {
+            self.epoch_work.get(router_addr)
+        }
+
+        /// get the snapshot of a closed epoch
+        #[ink(message)]
+        pub fn get_epoch_snapshot(&self, epoch: u32) -> Option<EpochSnapshot> {
+            self.epoch_snapshots.get(epoch)
+        }
+
+        /// get the reward of the router in a closed epoch
+        #[ink(message)]
+        pub fn get_epoch_reward(&self, epoch: u32, router_addr: AccountId) -> Option<EpochReward> {
+            self.epoch_rewards.get((epoch, router_addr))
+        }
+
+        /// get at most `limit` routers which have delivered messages in the epoch, starting from the position `from`
+        #[ink(message)]
+        pub fn get_epoch_routers(&self, epoch: u32, from: u32, limit: u32) -> ink::prelude::vec::Vec<AccountId> {
+            let count = if epoch == self.epoch {
+                self.epoch_router_count
+            } else {
+                self.epoch_snapshots.get(epoch).map(|snapshot| snapshot.routers).unwrap_or(0)
+            };
+
+            (from..count.min(from.saturating_add(limit)))
+                .filter_map(|position| self.epoch_routers.get((epoch, position)))
+                .collect()
+        }
+
         /// get the owner.
         #[ink(message)]
         pub fn get_owner(&self) -> AccountId {
@@ -222,6 +789,12 @@ mod tokenomics {
         pub fn get_protocol_addr(&self) -> Option<AccountId> {
             self.ps_contract
         }
+
+        /// get the total staking amount of all the routers, and the total amount of the pending withdrawals
+        #[ink(message)]
+        pub fn get_total_staking(&self) -> (u128, u128) {
+            (self.total, self.total_unbonding)
+        }
     }
 
     /// Unit tests in Rust are normally defined within such a `#[cfg(test)]`
@@ -232,13 +805,495 @@ mod tokenomics {
         /// Imports all the definitions from the outer scope so we can use them here.
         use super::*;
 
-        /// Imports `ink_lang` so we can use `#[ink::test]`.
-        use ink_lang as ink;
-
         /// We test a simple use case of our contract.
         #[ink::test]
         fn it_works() {
-            
+            let tokenomics = Tokenomics::new();
+            assert_eq!(tokenomics.get_protocol_addr(), None);
+        }
+
+        fn accounts() -> ink::env::test::DefaultAccounts<Environment> {
+            ink::env::test::default_accounts::<Environment>()
+        }
+
+        fn contract_id() -> AccountId {
+            ink::env::account_id::<Environment>()
+        }
+
+        fn balance_of(account: AccountId) -> Balance {
+            ink::env::test::get_account_balance::<Environment>(account).unwrap_or(0)
+        }
+
+        /// Call `pledge` by `router` with `value` transferred to the contract, as the runtime does
+        fn pledge(tokenomics: &mut Tokenomics, router: AccountId, value: Balance) -> Result<(), StakingError> {
+            ink::env::test::set_caller::<Environment>(router);
+            ink::env::pay_with_call!(tokenomics.pledge(), value)
+        }
+
+        fn staking_of(tokenomics: &Tokenomics, router: AccountId) -> u128 {
+            tokenomics.get_staking_info(router).map(|info| info.amount).unwrap_or(0)
+        }
+
+        #[ink::test]
+        fn pledge_takes_the_transferred_value() {
+            let bob = accounts().bob;
+            let mut tokenomics = Tokenomics::new();
+
+            assert_eq!(pledge(&mut tokenomics, bob, 0), Err(StakingError::ParamInvalid));
+            assert_eq!(tokenomics.get_staking_info(bob), None);
+
+            assert_eq!(pledge(&mut tokenomics, bob, 300), Ok(()));
+            assert_eq!(pledge(&mut tokenomics, bob, 200), Ok(()));
+            assert_eq!(staking_of(&tokenomics, bob), 500);
+            assert_eq!(tokenomics.get_total_staking(), (500, 0));
+
+            // registering again keeps the stake
+            tokenomics.register_router();
+            assert_eq!(staking_of(&tokenomics, bob), 500);
+        }
+
+        fn advance_blocks(blocks: BlockNumber) {
+            for _ in 0..blocks {
+                ink::env::test::advance_block::<Environment>();
+            }
+        }
+
+        #[ink::test]
+        fn withdrawal_waits_for_the_unbonding_period() {
+            let bob = accounts().bob;
+            let mut tokenomics = Tokenomics::new();
+            assert_eq!(pledge(&mut tokenomics, bob, 1000), Ok(()));
+            let weights = tokenomics.get_staking_weights(bob);
+            let balance = balance_of(bob);
+
+            assert_eq!(tokenomics.withdraw(1001), Err(StakingError::NotEnough));
+            assert_eq!(tokenomics.withdraw(600), Ok(()));
+            assert_eq!(tokenomics.get_unbonding(bob), ink::prelude::vec![UnbondingChunk {
+                amount: 600,
+                unlock_at: 100,
+            }]);
+            assert_eq!(tokenomics.get_total_staking(), (400, 600));
+
+            // the pending withdrawal does not count in the staking weights
+            assert_eq!(staking_of(&tokenomics, bob), 400);
+            assert!(tokenomics.get_staking_weights(bob) < weights);
+
+            advance_blocks(99);
+            assert_eq!(tokenomics.claim_unbonded(), Err(StakingError::NothingToClaim));
+
+            advance_blocks(1);
+            assert_eq!(tokenomics.withdraw(100), Ok(()));
+            assert_eq!(tokenomics.claim_unbonded(), Ok(600));
+            assert_eq!(balance_of(bob), balance + 600);
+            assert_eq!(tokenomics.get_unbonding(bob).len(), 1);
+            assert_eq!(tokenomics.get_total_staking(), (300, 100));
+
+            ink::env::test::set_caller::<Environment>(accounts().charlie);
+            assert_eq!(tokenomics.withdraw(1), Err(StakingError::NotExist));
+            assert_eq!(tokenomics.claim_unbonded(), Err(StakingError::NothingToClaim));
+        }
+
+        #[ink::test]
+        fn pending_withdrawals_are_bounded() {
+            let bob = accounts().bob;
+            let mut tokenomics = Tokenomics::new();
+            assert_eq!(pledge(&mut tokenomics, bob, 1000), Ok(()));
+
+            for _ in 0..MAX_UNBONDING_CHUNKS {
+                assert_eq!(tokenomics.withdraw(1), Ok(()));
+            }
+            assert_eq!(tokenomics.withdraw(1), Err(StakingError::TooManyUnbonding));
+
+            advance_blocks(100);
+            assert_eq!(tokenomics.claim_unbonded(), Ok(MAX_UNBONDING_CHUNKS as u128));
+            assert_eq!(tokenomics.withdraw(1), Ok(()));
+        }
+
+        #[ink::test]
+        fn unbonding_period_is_set_by_the_owner() {
+            let accounts = accounts();
+            let mut tokenomics = Tokenomics::new();
+
+            ink::env::test::set_caller::<Environment>(accounts.bob);
+            assert_eq!(tokenomics.set_unbonding_period(0), Err(StakingError::CallerInvalid));
+
+            ink::env::test::set_caller::<Environment>(accounts.alice);
+            assert_eq!(tokenomics.set_unbonding_period(0), Ok(()));
+            assert_eq!(tokenomics.get_unbonding_period(), 0);
+
+            // the withdrawal can be claimed at once
+            assert_eq!(pledge(&mut tokenomics, accounts.bob, 500), Ok(()));
+            assert_eq!(tokenomics.withdraw(500), Ok(()));
+            assert_eq!(tokenomics.claim_unbonded(), Ok(500));
+        }
+
+        #[ink::test]
+        fn failed_transfer_keeps_the_withdrawal() {
+            let bob = accounts().bob;
+            let mut tokenomics = Tokenomics::new();
+            assert_eq!(pledge(&mut tokenomics, bob, 500), Ok(()));
+            assert_eq!(tokenomics.withdraw(200), Ok(()));
+            advance_blocks(100);
+            let balance = balance_of(bob);
+
+            // the contract does not hold the tokens it owes
+            ink::env::test::set_account_balance::<Environment>(contract_id(), 100);
+            assert_eq!(tokenomics.claim_unbonded(), Err(StakingError::TransferFailed));
+            assert_eq!(balance_of(bob), balance);
+            assert_eq!(tokenomics.get_unbonding(bob).len(), 1);
+            assert_eq!(tokenomics.get_total_staking(), (300, 200));
+        }
+
+        /// Set charlie as the protocol stack contract and `bob` pledges `value`
+        fn staked_bob(value: Balance) -> Tokenomics {
+            let accounts = accounts();
+            let mut tokenomics = Tokenomics::new();
+            assert_eq!(tokenomics.set_protocol_stack(accounts.charlie), Ok(()));
+            assert_eq!(pledge(&mut tokenomics, accounts.bob, value), Ok(()));
+            tokenomics
+        }
+
+        #[ink::test]
+        fn slash_is_restricted_to_the_protocol_stack() {
+            let accounts = accounts();
+            let mut tokenomics = Tokenomics::new();
+            assert_eq!(pledge(&mut tokenomics, accounts.bob, 500), Ok(()));
+
+            // not set yet
+            assert_eq!(tokenomics.slash(accounts.bob, SlashAmount::Amount(100), 1, None), Err(StakingError::CallerInvalid));
+            assert_eq!(tokenomics.reward(accounts.bob, 100), Err(StakingError::CallerInvalid));
+
+            ink::env::test::set_caller::<Environment>(accounts.alice);
+            assert_eq!(tokenomics.set_protocol_stack(accounts.charlie), Ok(()));
+            assert_eq!(tokenomics.slash(accounts.bob, SlashAmount::Amount(100), 1, None), Err(StakingError::CallerInvalid));
+
+            ink::env::test::set_caller::<Environment>(accounts.charlie);
+            assert_eq!(tokenomics.slash(accounts.django, SlashAmount::Amount(100), 1, None), Err(StakingError::NotExist));
+            assert_eq!(tokenomics.slash(accounts.bob, SlashAmount::Fraction(RATIO_BASE + 1), 1, None), Err(StakingError::ParamInvalid));
+            assert_eq!(tokenomics.slash(accounts.bob, SlashAmount::Amount(100), 1, None), Ok(100));
+            assert_eq!(staking_of(&tokenomics, accounts.bob), 400);
+        }
+
+        #[ink::test]
+        fn slash_takes_the_stake_before_the_pending_withdrawals() {
+            let bob = accounts().bob;
+            let mut tokenomics = staked_bob(1000);
+            assert_eq!(tokenomics.withdraw(200), Ok(()));
+            assert_eq!(tokenomics.withdraw(100), Ok(()));
+
+            ink::env::test::set_caller::<Environment>(accounts().charlie);
+            assert_eq!(tokenomics.slash(bob, SlashAmount::Fraction(RATIO_BASE / 2), 1, None), Ok(500));
+            assert_eq!(staking_of(&tokenomics, bob), 200);
+            assert_eq!(tokenomics.get_total_staking(), (200, 300));
+
+            // the latest pending withdrawal is taken first
+            assert_eq!(tokenomics.slash(bob, SlashAmount::Amount(350), 2, None), Ok(350));
+            assert_eq!(staking_of(&tokenomics, bob), 0);
+            assert_eq!(tokenomics.get_unbonding(bob), ink::prelude::vec![UnbondingChunk {
+                amount: 150,
+                unlock_at: 100,
+            }]);
+            assert_eq!(tokenomics.get_total_staking(), (0, 150));
+
+            // capped at what is left
+            assert_eq!(tokenomics.slash(bob, SlashAmount::Amount(1000), 2, None), Ok(150));
+            assert_eq!(tokenomics.get_unbonding(bob), ink::prelude::vec![]);
+            assert_eq!(tokenomics.slash(bob, SlashAmount::Amount(1000), 2, None), Ok(0));
+            assert_eq!(tokenomics.get_total_locked(), 1000);
+
+            // no event if nothing is slashed
+            assert_eq!(ink::env::test::recorded_events().count(), 3);
+        }
+
+        #[ink::test]
+        fn slashed_funds_are_split() {
+            let accounts = accounts();
+            let mut tokenomics = staked_bob(1000);
+
+            ink::env::test::set_caller::<Environment>(accounts.alice);
+            let mut params = SlashParams {
+                treasury: Some(accounts.django),
+                treasury_ratio: 6000,
+                reporter_ratio: 5000,
+            };
+            assert_eq!(tokenomics.set_slash_params(params.clone()), Err(StakingError::ParamInvalid));
+            params.treasury_ratio = 5000;
+            params.reporter_ratio = 2000;
+            assert_eq!(tokenomics.set_slash_params(params.clone()), Ok(()));
+            assert_eq!(tokenomics.get_slash_params(), params);
+
+            ink::env::test::set_caller::<Environment>(accounts.bob);
+            assert_eq!(tokenomics.set_slash_params(params), Err(StakingError::CallerInvalid));
+
+            ink::env::test::set_caller::<Environment>(accounts.charlie);
+            assert_eq!(tokenomics.slash(accounts.bob, SlashAmount::Amount(600), 1, Some(accounts.eve)), Ok(600));
+            assert_eq!(balance_of(accounts.django), 300);
+            assert_eq!(balance_of(accounts.eve), 120);
+            assert_eq!(tokenomics.get_total_locked(), 180);
+
+            // without a reporter its share is locked
+            assert_eq!(tokenomics.slash(accounts.bob, SlashAmount::Amount(400), 1, None), Ok(400));
+            assert_eq!(balance_of(accounts.django), 500);
+            assert_eq!(balance_of(accounts.eve), 120);
+            assert_eq!(tokenomics.get_total_locked(), 380);
+        }
+
+        #[ink::test]
+        fn failed_transfer_keeps_the_slashed_stake() {
+            let accounts = accounts();
+            let mut tokenomics = staked_bob(500);
+
+            ink::env::test::set_caller::<Environment>(accounts.alice);
+            assert_eq!(tokenomics.set_slash_params(SlashParams {
+                treasury: Some(accounts.django),
+                treasury_ratio: RATIO_BASE,
+                reporter_ratio: 0,
+            }), Ok(()));
+
+            ink::env::test::set_account_balance::<Environment>(contract_id(), 100);
+            ink::env::test::set_caller::<Environment>(accounts.charlie);
+            assert_eq!(tokenomics.slash(accounts.bob, SlashAmount::Amount(200), 1, None), Err(StakingError::TransferFailed));
+            assert_eq!(staking_of(&tokenomics, accounts.bob), 500);
+            assert_eq!(balance_of(accounts.django), 0);
+        }
+
+        #[ink::test]
+        fn locked_funds_cannot_be_spent() {
+            let accounts = accounts();
+            let mut tokenomics = staked_bob(1000);
+            ink::env::test::set_account_balance::<Environment>(accounts.django, 300);
+            assert_eq!(pledge(&mut tokenomics, accounts.django, 300), Ok(()));
+
+            // everything slashed is locked by default
+            ink::env::test::set_caller::<Environment>(accounts.charlie);
+            assert_eq!(tokenomics.slash(accounts.bob, SlashAmount::Fraction(RATIO_BASE), 1, None), Ok(1000));
+            assert_eq!(tokenomics.get_total_locked(), 1000);
+
+            ink::env::test::set_caller::<Environment>(accounts.django);
+            assert_eq!(tokenomics.withdraw(300), Ok(()));
+            advance_blocks(tokenomics.get_unbonding_period());
+
+            // the withdrawal cannot be paid from the locked funds
+            let minimum = ink::env::minimum_balance::<Environment>();
+            ink::env::test::set_account_balance::<Environment>(contract_id(), minimum + 1000 + 299);
+            assert_eq!(tokenomics.claim_unbonded(), Err(StakingError::TransferFailed));
+
+            ink::env::test::set_account_balance::<Environment>(contract_id(), minimum + 1000 + 300);
+            assert_eq!(tokenomics.claim_unbonded(), Ok(300));
+            assert_eq!(balance_of(accounts.django), 300);
+            assert_eq!(balance_of(contract_id()), minimum + 1000);
+        }
+
+        /// Fund the reward pool of `tokenomics` with `value` transferred by alice
+        fn fund(tokenomics: &mut Tokenomics, value: Balance) -> Result<(), StakingError> {
+            ink::env::test::set_caller::<Environment>(accounts().alice);
+            ink::env::pay_with_call!(tokenomics.fund_reward_pool(), value)
+        }
+
+        #[ink::test]
+        fn rewards_are_backed_by_the_pool() {
+            let accounts = accounts();
+            let mut tokenomics = staked_bob(500);
+
+            assert_eq!(fund(&mut tokenomics, 0), Err(StakingError::ParamInvalid));
+            assert_eq!(fund(&mut tokenomics, 250), Ok(()));
+            assert_eq!(tokenomics.get_reward_pool(), (250, 0));
+
+            ink::env::test::set_caller::<Environment>(accounts.charlie);
+            assert_eq!(tokenomics.reward(accounts.bob, 50), Ok(()));
+            assert_eq!(tokenomics.reward(accounts.bob, 100), Ok(()));
+            assert_eq!(tokenomics.get_reward_pool(), (0, 250));
+            assert_eq!(tokenomics.get_staking_info(accounts.bob).map(|info| info.reward), Some(250));
+
+            // nothing is promised once the pool is empty
+            assert_eq!(tokenomics.reward(accounts.bob, 100), Ok(()));
+            assert_eq!(tokenomics.get_reward_pool(), (0, 250));
+        }
+
+        #[ink::test]
+        fn claim_rewards_pays_out_or_compounds() {
+            let accounts = accounts();
+            let mut tokenomics = staked_bob(500);
+            assert_eq!(fund(&mut tokenomics, 1000), Ok(()));
+
+            ink::env::test::set_caller::<Environment>(accounts.bob);
+            assert_eq!(tokenomics.claim_rewards(false), Err(StakingError::NothingToClaim));
+
+            ink::env::test::set_caller::<Environment>(accounts.charlie);
+            assert_eq!(tokenomics.reward(accounts.bob, 50), Ok(()));
+
+            ink::env::test::set_caller::<Environment>(accounts.bob);
+            let balance = balance_of(accounts.bob);
+            assert_eq!(tokenomics.claim_rewards(false), Ok(100));
+            assert_eq!(balance_of(accounts.bob), balance + 100);
+            assert_eq!(tokenomics.get_staking_info(accounts.bob).map(|info| info.reward), Some(0));
+            assert_eq!(tokenomics.get_reward_pool(), (900, 0));
+
+            ink::env::test::set_caller::<Environment>(accounts.charlie);
+            assert_eq!(tokenomics.reward(accounts.bob, 50), Ok(()));
+
+            ink::env::test::set_caller::<Environment>(accounts.bob);
+            assert_eq!(tokenomics.claim_rewards(true), Ok(100));
+            assert_eq!(balance_of(accounts.bob), balance + 100);
+            assert_eq!(staking_of(&tokenomics, accounts.bob), 600);
+            assert_eq!(tokenomics.get_total_staking(), (600, 0));
+            assert_eq!(tokenomics.get_reward_pool(), (800, 0));
+
+            ink::env::test::set_caller::<Environment>(accounts.django);
+            assert_eq!(tokenomics.claim_rewards(false), Err(StakingError::NotExist));
+        }
+
+        #[ink::test]
+        fn failed_transfer_keeps_the_rewards() {
+            let accounts = accounts();
+            let mut tokenomics = staked_bob(500);
+            assert_eq!(fund(&mut tokenomics, 1000), Ok(()));
+            ink::env::test::set_caller::<Environment>(accounts.charlie);
+            assert_eq!(tokenomics.reward(accounts.bob, 50), Ok(()));
+
+            ink::env::test::set_account_balance::<Environment>(contract_id(), 100);
+            ink::env::test::set_caller::<Environment>(accounts.bob);
+            assert_eq!(tokenomics.claim_rewards(false), Err(StakingError::TransferFailed));
+            assert_eq!(tokenomics.get_staking_info(accounts.bob).map(|info| info.reward), Some(100));
+            assert_eq!(tokenomics.get_reward_pool(), (900, 100));
+        }
+
+        fn advance_epoch(tokenomics: &Tokenomics) {
+            advance_blocks(tokenomics.get_epoch_params().0);
+        }
+
+        #[ink::test]
+        fn epoch_emission_is_split_by_work_credibility_and_stake() {
+            let accounts = accounts();
+            let mut tokenomics = staked_bob(1000);
+            assert_eq!(fund(&mut tokenomics, 1000), Ok(()));
+            ink::env::test::set_caller::<Environment>(accounts.django);
+            tokenomics.register_router();
+
+            assert_eq!(tokenomics.record_delivery(accounts.bob, 80), Err(StakingError::CallerInvalid));
+
+            ink::env::test::set_caller::<Environment>(accounts.charlie);
+            assert_eq!(tokenomics.record_delivery(accounts.bob, 101), Err(StakingError::ParamInvalid));
+            assert_eq!(tokenomics.record_delivery(accounts.eve, 80), Err(StakingError::NotExist));
+            assert_eq!(tokenomics.record_delivery(accounts.bob, 80), Ok(()));
+            assert_eq!(tokenomics.record_delivery(accounts.bob, 80), Ok(()));
+            assert_eq!(tokenomics.record_delivery(accounts.django, 100), Ok(()));
+            assert_eq!(tokenomics.get_epoch_work(accounts.bob), Some(EpochWork {
+                deliveries: 2,
+                cred_sum: 160,
+            }));
+
+            assert_eq!(tokenomics.close_epoch(), Err(StakingError::EpochNotEnded));
+            advance_epoch(&tokenomics);
+            assert_eq!(tokenomics.close_epoch(), Ok(0));
+            assert_eq!(tokenomics.get_current_epoch(), (1, 1000));
+            assert_eq!(tokenomics.get_epoch_work(accounts.bob), None);
+
+            // bob: 160 * (100 + 50), django without stake: 100 * 100
+            let reward_of = |router| tokenomics.get_staking_info(router).map(|info| info.reward);
+            assert_eq!(reward_of(accounts.bob), Some(705));
+            assert_eq!(reward_of(accounts.django), Some(294));
+            assert_eq!(tokenomics.get_reward_pool(), (1, 999));
+
+            let snapshot = tokenomics.get_epoch_snapshot(0).unwrap();
+            assert_eq!((snapshot.start, snapshot.end), (0, 1000));
+            assert_eq!((snapshot.emission, snapshot.distributed, snapshot.total_score), (1000, 999, 34000));
+            assert_eq!(snapshot.routers, 2);
+            assert_eq!(tokenomics.get_epoch_routers(0, 0, 10), ink::prelude::vec![accounts.bob, accounts.django]);
+            assert_eq!(tokenomics.get_epoch_reward(0, accounts.bob), Some(EpochReward {
+                work: EpochWork {
+                    deliveries: 2,
+                    cred_sum: 160,
+                },
+                staking_weight: 50,
+                score: 24000,
+                reward: 705,
+            }));
+            assert_eq!(tokenomics.get_epoch_snapshot(1), None);
+            assert_eq!(ink::env::test::recorded_events().count(), 1);
+        }
+
+        #[ink::test]
+        fn epoch_emission_is_capped_at_the_pool() {
+            let accounts = accounts();
+            let mut tokenomics = staked_bob(1000);
+            assert_eq!(fund(&mut tokenomics, 300), Ok(()));
+
+            // an epoch without deliveries takes nothing
+            advance_epoch(&tokenomics);
+            assert_eq!(tokenomics.close_epoch(), Ok(0));
+            assert_eq!(tokenomics.get_epoch_snapshot(0).map(|snapshot| snapshot.emission), Some(0));
+            assert_eq!(tokenomics.get_reward_pool(), (300, 0));
+
+            ink::env::test::set_caller::<Environment>(accounts.charlie);
+            assert_eq!(tokenomics.record_delivery(accounts.bob, 50), Ok(()));
+            advance_epoch(&tokenomics);
+            assert_eq!(tokenomics.close_epoch(), Ok(1));
+            assert_eq!(tokenomics.get_epoch_snapshot(1).map(|snapshot| snapshot.distributed), Some(300));
+            assert_eq!(tokenomics.get_reward_pool(), (0, 300));
+        }
+
+        #[ink::test]
+        fn epoch_params_are_set_by_the_owner() {
+            let accounts = accounts();
+            let mut tokenomics = staked_bob(1000);
+
+            ink::env::test::set_caller::<Environment>(accounts.bob);
+            assert_eq!(tokenomics.set_epoch_params(10, 100), Err(StakingError::CallerInvalid));
+
+            ink::env::test::set_caller::<Environment>(accounts.alice);
+            assert_eq!(tokenomics.set_epoch_params(0, 100), Err(StakingError::ParamInvalid));
+            assert_eq!(tokenomics.set_epoch_params(10, 100), Ok(()));
+            assert_eq!(tokenomics.get_epoch_params(), (10, 100));
+
+            advance_blocks(9);
+            assert_eq!(tokenomics.close_epoch(), Err(StakingError::EpochNotEnded));
+            advance_blocks(1);
+            assert_eq!(tokenomics.close_epoch(), Ok(0));
+        }
+
+        #[ink::test]
+        fn a_full_epoch_can_be_closed() {
+            let mut tokenomics = staked_bob(1000);
+            assert_eq!(fund(&mut tokenomics, 1000), Ok(()));
+            let routers: ink::prelude::vec::Vec<AccountId> = (0..MAX_EPOCH_ROUTERS)
+                .map(|i| {
+                    let mut id = [0xaa; 32];
+                    id[..4].copy_from_slice(&i.to_be_bytes());
+                    AccountId::from(id)
+                })
+                .collect();
+            for router in routers.iter() {
+                ink::env::test::set_caller::<Environment>(*router);
+                tokenomics.register_router();
+            }
+
+            ink::env::test::set_caller::<Environment>(accounts().charlie);
+            for router in routers.iter() {
+                assert_eq!(tokenomics.record_delivery(*router, 50), Ok(()));
+            }
+            assert_eq!(tokenomics.record_delivery(accounts().bob, 50), Err(StakingError::EpochFull));
+            // the routers recorded can still deliver
+            assert_eq!(tokenomics.record_delivery(routers[0], 50), Ok(()));
+
+            advance_epoch(&tokenomics);
+            assert_eq!(tokenomics.close_epoch(), Ok(0));
+
+            // 1000 * 10000 / 1285000 for the first router, and 1000 * 5000 / 1285000 for the others
+            let snapshot = tokenomics.get_epoch_snapshot(0).unwrap();
+            assert_eq!((snapshot.routers, snapshot.total_score, snapshot.distributed), (MAX_EPOCH_ROUTERS, 1285000, 7 + 255 * 3));
+            assert_eq!(tokenomics.get_epoch_reward(0, routers[0]).map(|item| item.reward), Some(7));
+            assert_eq!(tokenomics.get_epoch_reward(0, routers[255]).map(|item| item.reward), Some(3));
+            assert_eq!(tokenomics.get_epoch_routers(0, 0, MAX_EPOCH_ROUTERS + 1), routers);
+            assert_eq!(tokenomics.get_epoch_routers(0, 250, 10), routers[250..].to_vec());
+
+            // ink encodes a storage value into a buffer of 16 KiB, the records of an epoch do not grow with the routers
+            assert!(scale::Encode::encoded_size(&snapshot) < 128);
+
+            // the next epoch starts empty
+            assert_eq!(tokenomics.get_epoch_routers(1, 0, MAX_EPOCH_ROUTERS), ink::prelude::vec![]);
+            assert_eq!(tokenomics.record_delivery(accounts().bob, 50), Ok(()));
+            assert_eq!(tokenomics.get_epoch_routers(1, 0, MAX_EPOCH_ROUTERS), ink::prelude::vec![accounts().bob]);
         }
     }
 }
@@ -1,14 +1,17 @@
 #!/bin/bash
+set -e
 
-cargo +nightly test
+cd "$(dirname "$0")"
 
-export PATH=~/env/rust/binaryen-version_105/bin/:$PATH
+cargo build --workspace
 
-cargo +nightly contract build
+cargo test --workspace
 
-cargo +nightly contract build --release
-
-cargo +nightly test
+# the contracts of the workspace
+for contract in contracts/*/; do
+    cargo contract build --manifest-path "${contract}Cargo.toml"
+    cargo contract build --release --manifest-path "${contract}Cargo.toml"
+done
 
 # code improvement
-cargo +nightly clippy -- -A clippy::let_unit_value
+cargo clippy --workspace --all-targets -- -D warnings
@@ -0,0 +1,17 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+//! Message payload and message definitions of the Dante protocol stack
+//!
+//! Vendored from the `crypto-dev` branch of [message-ink](https://github.com/dantenetwork/message-ink/tree/crypto-dev),
+//! such that the contracts of the workspace build without a sibling checkout
+
+pub mod message_define;
+pub mod message_protocol;
+
+/// Test data used by the examples
+#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+pub struct TestData {
+    pub n: u128,
+    pub s: ink::prelude::string::String,
+}
@@ -0,0 +1,178 @@
+//! The messages sent and received through the protocol stack
+
+use ink::prelude::string::String;
+use ink::prelude::vec::Vec;
+
+use crate::message_protocol::MessagePayload;
+
+/// The types of the SQoS items
+#[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+pub enum ISQoSType {
+    Reveal,
+    Challenge,
+    Threshold,
+    Priority,
+    ExceptionRollback,
+    SelectionDelay,
+    Anonymous,
+    Identity,
+    Isolation,
+    CrossVerify,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+pub struct ISQoS {
+    pub t: ISQoSType,
+    pub v: Option<Vec<u8>>,
+}
+
+impl ISQoS {
+    pub fn into_raw_data(&self) -> Vec<u8> {
+        let mut raw_data = ink::prelude::vec![self.t as u8];
+        if let Some(v) = &self.v {
+            raw_data.extend_from_slice(v);
+        }
+
+        raw_data
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+pub struct ISession {
+    pub id: u128,
+    pub callback: Vec<u8>,
+}
+
+impl ISession {
+    pub fn into_raw_data(&self) -> Vec<u8> {
+        let mut raw_data = Vec::from(self.id.to_be_bytes());
+        raw_data.extend_from_slice(&self.callback);
+
+        raw_data
+    }
+}
+
+/// The message sent to another chain
+#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+pub struct ISentMessage {
+    pub to_chain: String,
+    pub sqos: Vec<ISQoS>,
+    pub content: IContent,
+    pub session: ISession,
+}
+
+/// The target and the payload of a sent message
+#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+pub struct IContent {
+    pub contract: String,
+    pub action: String,
+    pub data: MessagePayload,
+}
+
+/// The message received from another chain
+#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+pub struct IReceivedMessage {
+    pub id: u128,
+    pub from_chain: String,
+    pub to_chain: String,
+    pub sender: Vec<u8>,
+    pub signer: Vec<u8>,
+    pub sqos: Vec<ISQoS>,
+    pub contract: [u8; 32],
+    pub action: [u8; 4],
+    pub data: Vec<u8>,
+    pub session: ISession,
+}
+
+impl IReceivedMessage {
+    /// The raw bytes the hash of the message is computed from, which are the fields concatenated in order,
+    /// with the numbers in big-endian and the strings in UTF-8
+    pub fn into_raw_data(&self) -> Vec<u8> {
+        let mut raw_data = Vec::from(self.id.to_be_bytes());
+        raw_data.extend_from_slice(self.from_chain.as_bytes());
+        raw_data.extend_from_slice(self.to_chain.as_bytes());
+        raw_data.extend_from_slice(&self.sender);
+        raw_data.extend_from_slice(&self.signer);
+        for sqos in self.sqos.iter() {
+            raw_data.append(&mut sqos.into_raw_data());
+        }
+        raw_data.extend_from_slice(&self.contract);
+        raw_data.extend_from_slice(&self.action);
+        raw_data.extend_from_slice(&self.data);
+        raw_data.append(&mut self.session.into_raw_data());
+
+        raw_data
+    }
+
+    /// The hash of the raw data of the message
+    pub fn into_hash<H>(&self) -> [u8; 32]
+    where
+        H: ink::env::hash::CryptoHash + ink::env::hash::HashOutput<Type = [u8; 32]>,
+    {
+        let mut output = <H as ink::env::hash::HashOutput>::Type::default();
+        ink::env::hash_bytes::<H>(&self.into_raw_data(), &mut output);
+        output
+    }
+
+    /// The SCALE encoded message
+    pub fn into_bytes(&self) -> Vec<u8> {
+        scale::Encode::encode(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn received() -> IReceivedMessage {
+        IReceivedMessage {
+            id: 1,
+            from_chain: String::from("A"),
+            to_chain: String::from("B"),
+            sender: ink::prelude::vec![2],
+            signer: ink::prelude::vec![3],
+            sqos: ink::prelude::vec![ISQoS {
+                t: ISQoSType::Threshold,
+                v: Some(ink::prelude::vec![4]),
+            }],
+            contract: [5; 32],
+            action: [6; 4],
+            data: ink::prelude::vec![7],
+            session: ISession {
+                id: 8,
+                callback: ink::prelude::vec![9],
+            },
+        }
+    }
+
+    #[test]
+    fn raw_data_concatenates_fields_in_order() {
+        let mut expected = Vec::from(1u128.to_be_bytes());
+        expected.extend_from_slice(b"AB");
+        expected.extend_from_slice(&[2, 3, ISQoSType::Threshold as u8, 4]);
+        expected.extend_from_slice(&[5; 32]);
+        expected.extend_from_slice(&[6; 4]);
+        expected.push(7);
+        expected.extend_from_slice(&8u128.to_be_bytes());
+        expected.push(9);
+
+        assert_eq!(received().into_raw_data(), expected);
+    }
+
+    #[test]
+    fn hash_covers_every_field() {
+        let msg = received();
+        let hash = msg.into_hash::<ink::env::hash::Keccak256>();
+
+        let mut other = received();
+        other.session.callback = ink::prelude::vec![10];
+        assert_ne!(other.into_hash::<ink::env::hash::Keccak256>(), hash);
+        assert_eq!(received().into_hash::<ink::env::hash::Keccak256>(), hash);
+    }
+}
@@ -0,0 +1,204 @@
+//! The payload of messages, which is a list of named items
+
+use ink::prelude::string::String;
+use ink::prelude::vec::Vec;
+
+/// The address on ink! chains
+#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+pub struct InkAddressData {
+    pub ink_address: Vec<u8>,
+    pub address_type: u8,
+}
+
+/// The typed value of a message item
+#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+pub enum MsgDetail {
+    InkString(String),
+    InkU8(u8),
+    InkU16(u16),
+    InkU32(u32),
+    InkU64(u64),
+    InkU128(u128),
+    InkI8(i8),
+    InkI16(i16),
+    InkI32(i32),
+    InkI64(i64),
+    InkI128(i128),
+    InkStringArray(Vec<String>),
+    InkU8Array(Vec<u8>),
+    InkU16Array(Vec<u16>),
+    InkU32Array(Vec<u32>),
+    InkU64Array(Vec<u64>),
+    InkU128Array(Vec<u128>),
+    InkI8Array(Vec<i8>),
+    InkI16Array(Vec<i16>),
+    InkI32Array(Vec<i32>),
+    InkI64Array(Vec<i64>),
+    InkI128Array(Vec<i128>),
+    InkAddress(InkAddressData),
+    UserData(Vec<u8>),
+}
+
+/// Types that can be read from and written into a `MsgDetail`
+pub trait InMsgType {
+    type MyType;
+    fn get_value(type_value: &MsgDetail) -> Option<Self::MyType>;
+    fn create_message(msg_detail: Self::MyType) -> MsgDetail;
+    /// The raw bytes used to compute the hash of a message
+    fn into_raw_data(self) -> Vec<u8>;
+}
+
+macro_rules! impl_number_msg_type {
+    ($($t:ty => $variant:ident),*) => {
+        $(
+            impl InMsgType for $t {
+                type MyType = $t;
+                fn get_value(type_value: &MsgDetail) -> Option<Self::MyType> {
+                    if let MsgDetail::$variant(val) = type_value {
+                        Some(*val)
+                    } else {
+                        None
+                    }
+                }
+
+                fn create_message(msg_detail: Self::MyType) -> MsgDetail {
+                    MsgDetail::$variant(msg_detail)
+                }
+
+                fn into_raw_data(self) -> Vec<u8> {
+                    Vec::from(self.to_be_bytes())
+                }
+            }
+        )*
+    };
+}
+
+impl_number_msg_type!(
+    u8 => InkU8, u16 => InkU16, u32 => InkU32, u64 => InkU64, u128 => InkU128,
+    i8 => InkI8, i16 => InkI16, i32 => InkI32, i64 => InkI64, i128 => InkI128
+);
+
+impl InMsgType for String {
+    type MyType = String;
+    fn get_value(type_value: &MsgDetail) -> Option<Self::MyType> {
+        if let MsgDetail::InkString(val) = type_value {
+            Some(val.clone())
+        } else {
+            None
+        }
+    }
+
+    fn create_message(msg_detail: Self::MyType) -> MsgDetail {
+        MsgDetail::InkString(msg_detail)
+    }
+
+    fn into_raw_data(self) -> Vec<u8> {
+        self.into_bytes()
+    }
+}
+
+impl InMsgType for InkAddressData {
+    type MyType = InkAddressData;
+    fn get_value(type_value: &MsgDetail) -> Option<Self::MyType> {
+        if let MsgDetail::InkAddress(val) = type_value {
+            Some(val.clone())
+        } else {
+            None
+        }
+    }
+
+    fn create_message(msg_detail: Self::MyType) -> MsgDetail {
+        MsgDetail::InkAddress(msg_detail)
+    }
+
+    fn into_raw_data(self) -> Vec<u8> {
+        let mut raw_data = self.ink_address;
+        raw_data.push(self.address_type);
+        raw_data
+    }
+}
+
+/// A named item of the payload
+#[derive(Debug, Clone, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+pub struct MessageItem {
+    pub n: String,
+    pub tv: MsgDetail,
+}
+
+/// Items are identified by their names
+impl PartialEq for MessageItem {
+    fn eq(&self, other: &Self) -> bool {
+        self.n == other.n
+    }
+}
+
+impl MessageItem {
+    pub fn from(n: String, tv: MsgDetail) -> Self {
+        MessageItem { n, tv }
+    }
+
+    pub fn in_to<T: InMsgType>(&self) -> Option<T::MyType> {
+        T::get_value(&self.tv)
+    }
+}
+
+/// The payload of a message
+#[derive(Debug, Clone, Default, PartialEq, Eq, scale::Encode, scale::Decode)]
+#[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+pub struct MessagePayload {
+    pub items: Option<Vec<MessageItem>>,
+}
+
+impl MessagePayload {
+    pub fn new() -> Self {
+        MessagePayload { items: None }
+    }
+
+    /// Add an item, returns `false` if an item with the same name exists
+    pub fn push_item(&mut self, n: String, tv: MsgDetail) -> bool {
+        let items = self.items.get_or_insert_with(Vec::new);
+        if items.iter().any(|item| item.n == n) {
+            return false;
+        }
+
+        items.push(MessageItem { n, tv });
+        true
+    }
+
+    pub fn get_item(&self, n: String) -> Option<&MessageItem> {
+        self.items.as_ref()?.iter().find(|item| item.n == n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_item_keeps_names_unique() {
+        let mut payload = MessagePayload::new();
+        assert!(payload.push_item(String::from("1"), MsgDetail::InkU16(24)));
+        assert!(!payload.push_item(String::from("1"), MsgDetail::InkU8(255)));
+
+        let item = payload.get_item(String::from("1")).unwrap();
+        assert_eq!(item.tv, MsgDetail::InkU16(24));
+        assert_eq!(item.in_to::<u16>(), Some(24));
+        assert_eq!(item.in_to::<u8>(), None);
+        assert!(payload.get_item(String::from("2")).is_none());
+    }
+
+    #[test]
+    fn raw_data_is_big_endian() {
+        assert_eq!(0x0102_u16.into_raw_data(), ink::prelude::vec![1, 2]);
+        assert_eq!(String::from("ab").into_raw_data(), b"ab".to_vec());
+
+        let address = InkAddressData {
+            ink_address: ink::prelude::vec![1, 2, 3],
+            address_type: 4,
+        };
+        assert_eq!(address.into_raw_data(), ink::prelude::vec![1, 2, 3, 4]);
+    }
+}
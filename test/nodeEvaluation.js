@@ -6,6 +6,12 @@ import 'dotenv/config'
 
 const EvaluateResult = Struct({
     behavior_type: str,
+    model: Enum({
+        Piecewise: _void,
+        ExponentialDecay: _void,
+        AsymmetricPenalty: _void,
+        FloorCeiling: _void,
+    }),
     results: Vector(u32),
 });
 
@@ -26,7 +32,7 @@ async function do_honest() {
     // NOTE the apps UI specified these in mega units
     const gasLimit = -1;
 
-    await contract.tx.doHonest({ value, gasLimit }, 0, 20)
+    await contract.tx.doHonest({ value, gasLimit }, process.env.ROUTER, 20)
                     .signAndSend(sender, (result) => {
                         console.log('result', result.isInBlock, result.isFinalized, result.isError, result.isWarning);
                         // console.log(result.events);
@@ -56,7 +62,7 @@ async function do_evil() {
     // NOTE the apps UI specified these in mega units
     const gasLimit = -1;
 
-    await contract.tx.doEvil({ value, gasLimit }, 0, 20)
+    await contract.tx.doEvil({ value, gasLimit }, process.env.ROUTER, 20)
                     .signAndSend(sender, (result) => {
                         console.log('result', result.isInBlock, result.isFinalized, result.isError, result.isWarning);
                         // console.log(result.events);
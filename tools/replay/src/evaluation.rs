@@ -0,0 +1,170 @@
+//! Credibility curves by `evaluation.rs` of the contract
+//!
+//! The parameters are mirrored in serializable types of the scenario, and converted to the ones of the contract to evaluate a router.
+
+use algorithm_prototype::evaluation as contract;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EvaluationModel {
+    Piecewise,
+    ExponentialDecay,
+    AsymmetricPenalty,
+    FloorCeiling,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CredibilityRange {
+    pub min: u32,
+    pub middle: u32,
+    pub max: u32,
+}
+
+impl Default for CredibilityRange {
+    fn default() -> Self {
+        CredibilityRange {
+            min: 0,
+            middle: 50,
+            max: 100,
+        }
+    }
+}
+
+impl CredibilityRange {
+    pub fn range(&self) -> u32 {
+        self.max.saturating_sub(self.min)
+    }
+
+    pub fn clamp(&self, cred: u32) -> u32 {
+        cred.max(self.min).min(self.max)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EvaluationParams {
+    pub model: EvaluationModel,
+    pub gain_bps: u32,
+    pub decay_bps: u32,
+    pub repeat_penalty_bps: u32,
+    pub floor: u32,
+    pub ceiling: u32,
+    pub cooldown: u32,
+}
+
+impl From<&CredibilityRange> for contract::CredibilityRange {
+    fn from(range: &CredibilityRange) -> Self {
+        contract::CredibilityRange {
+            min: range.min,
+            middle: range.middle,
+            max: range.max,
+        }
+    }
+}
+
+impl From<EvaluationModel> for contract::EvaluationModel {
+    fn from(model: EvaluationModel) -> Self {
+        match model {
+            EvaluationModel::Piecewise => contract::EvaluationModel::Piecewise,
+            EvaluationModel::ExponentialDecay => contract::EvaluationModel::ExponentialDecay,
+            EvaluationModel::AsymmetricPenalty => contract::EvaluationModel::AsymmetricPenalty,
+            EvaluationModel::FloorCeiling => contract::EvaluationModel::FloorCeiling,
+        }
+    }
+}
+
+impl From<contract::EvaluationModel> for EvaluationModel {
+    fn from(model: contract::EvaluationModel) -> Self {
+        match model {
+            contract::EvaluationModel::Piecewise => EvaluationModel::Piecewise,
+            contract::EvaluationModel::ExponentialDecay => EvaluationModel::ExponentialDecay,
+            contract::EvaluationModel::AsymmetricPenalty => EvaluationModel::AsymmetricPenalty,
+            contract::EvaluationModel::FloorCeiling => EvaluationModel::FloorCeiling,
+        }
+    }
+}
+
+impl From<&EvaluationParams> for contract::EvaluationParams {
+    fn from(params: &EvaluationParams) -> Self {
+        contract::EvaluationParams {
+            model: params.model.into(),
+            gain_bps: params.gain_bps,
+            decay_bps: params.decay_bps,
+            repeat_penalty_bps: params.repeat_penalty_bps,
+            floor: params.floor,
+            ceiling: params.ceiling,
+            cooldown: params.cooldown,
+        }
+    }
+}
+
+impl From<contract::EvaluationParams> for EvaluationParams {
+    fn from(params: contract::EvaluationParams) -> Self {
+        EvaluationParams {
+            model: params.model.into(),
+            gain_bps: params.gain_bps,
+            decay_bps: params.decay_bps,
+            repeat_penalty_bps: params.repeat_penalty_bps,
+            floor: params.floor,
+            ceiling: params.ceiling,
+            cooldown: params.cooldown,
+        }
+    }
+}
+
+impl EvaluationParams {
+    pub fn piecewise(range: &CredibilityRange) -> Self {
+        contract::EvaluationParams::piecewise(&range.into()).into()
+    }
+
+    pub fn honest(&self, range: &CredibilityRange, cred: u32, blocks_since_offence: Option<u32>) -> u32 {
+        contract::EvaluationParams::from(self).honest(&range.into(), cred, blocks_since_offence)
+    }
+
+    pub fn evil(&self, range: &CredibilityRange, cred: u32, offences: u32) -> u32 {
+        contract::EvaluationParams::from(self).evil(&range.into(), cred, offences)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The same cases as the unit tests of the contract
+    #[test]
+    fn curves_match_the_contract() {
+        let range = CredibilityRange::default();
+        let mut params = EvaluationParams::piecewise(&range);
+
+        assert_eq!(params.honest(&range, 10, None), 11);
+        assert_eq!(params.honest(&range, 60, None), 64);
+        assert_eq!(params.evil(&range, 60, 0), 48);
+
+        params.model = EvaluationModel::AsymmetricPenalty;
+        assert_eq!(params.evil(&range, 60, 2), 36);
+
+        params.model = EvaluationModel::ExponentialDecay;
+        assert_eq!(params.honest(&range, 50, None), 55);
+        assert_eq!(params.evil(&range, 50, 0), 40);
+    }
+
+    #[test]
+    fn out_of_range_credibility_matches_the_contract() {
+        let range = CredibilityRange {
+            min: 60,
+            middle: 70,
+            max: 100,
+        };
+        let params = EvaluationParams::piecewise(&CredibilityRange::default());
+
+        assert_eq!(params.honest(&range, 50, None), 60);
+        assert_eq!(params.evil(&range, 50, 0), 60);
+        assert_eq!(params.honest(&CredibilityRange::default(), 120, None), 100);
+
+        let empty = CredibilityRange {
+            min: 50,
+            middle: 50,
+            max: 50,
+        };
+        assert_eq!(params.evil(&empty, 50, 0), 50);
+    }
+}
@@ -0,0 +1,33 @@
+//! Off-chain replay of the algorithm prototype
+//!
+//! A scenario is replayed on host types without a node. The selection, the seeds and the evaluation are computed by
+//! the modules of `contracts/algorithm` independent of the contract environment, so given the same seed and routers,
+//! the results are the same as the ones of the contract. The verification is re-implemented in `verification`.
+
+pub mod evaluation;
+pub mod randomness;
+pub mod scenario;
+pub mod selection;
+pub mod verification;
+
+/// Parse a `0x` prefixed hex string
+pub fn from_hex(hex: &str) -> Result<Vec<u8>, String> {
+    let hex = hex.strip_prefix("0x").unwrap_or(hex);
+    if !hex.len().is_multiple_of(2) {
+        return Err(format!("odd length hex string: {}", hex));
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|idx| u8::from_str_radix(&hex[idx..idx + 2], 16).map_err(|e| format!("invalid hex string {}: {}", hex, e)))
+        .collect()
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    let mut hex = String::from("0x");
+    for byte in bytes {
+        hex.push_str(&format!("{:02x}", byte));
+    }
+
+    hex
+}
@@ -0,0 +1,33 @@
+//! Replay a JSON scenario and print the outcome of every step
+//!
+//! Usage: `algorithm-replay <scenario.json>`
+
+use std::process;
+
+use algorithm_replay::scenario::{self, Scenario};
+
+fn main() {
+    let path = match std::env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("Usage: algorithm-replay <scenario.json>");
+            process::exit(2);
+        }
+    };
+
+    let scenario: Scenario = match std::fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string())) {
+        Ok(scenario) => scenario,
+        Err(e) => {
+            eprintln!("Failed to load {}: {}", path, e);
+            process::exit(2);
+        }
+    };
+
+    match scenario::replay(&scenario) {
+        Ok(outcomes) => println!("{}", serde_json::to_string_pretty(&outcomes).expect("outcomes are serializable")),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,44 @@
+//! Selection seeds by `randomness.rs` of the contract
+
+use scale::Encode;
+
+pub use algorithm_prototype::randomness::{
+    blake2, mix, SeededRandom, DOMAIN_INSPECTION_DRAW, DOMAIN_INSPECTORS, DOMAIN_ROUTERS, ROUND_INSPECTION, ROUND_SELECTION,
+};
+
+/// The seed of `round` of a message derived from the beacon of `CommitReveal`
+pub fn beacon_seed(beacon: &[u8; 32], from_chain: &str, msg_id: u128, round: u8) -> [u8; 32] {
+    let mut subject = (String::from(from_chain), msg_id).encode();
+    subject.push(round);
+    mix(beacon, &subject)
+}
+
+/// The seed of `Vrf` from the signature of the oracle
+pub fn vrf_seed(signature: &[u8]) -> [u8; 32] {
+    blake2(signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::selection::RandomSource;
+
+    #[test]
+    fn blake2_is_blake2b_256() {
+        assert_eq!(
+            crate::to_hex(&blake2(b"")),
+            "0x0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8"
+        );
+    }
+
+    #[test]
+    fn blocks_are_split_in_order() {
+        let seed = [7u8; 32];
+        let block = mix(&seed, &[DOMAIN_ROUTERS, 0, 0, 0, 0]);
+        let mut rng = SeededRandom::new(&seed, DOMAIN_ROUTERS);
+
+        assert_eq!(rng.next_u128().to_be_bytes(), block[..16]);
+        assert_eq!(rng.next_u128().to_be_bytes(), block[16..]);
+    }
+}
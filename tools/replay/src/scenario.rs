@@ -0,0 +1,477 @@
+//! Scenarios replayed step by step on a set of routers
+//!
+//! The routers are listed in the order they are registered in the contract,
+//! which is also the order of the slots of the selection weights, see `get_selection_weights`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::evaluation::{CredibilityRange, EvaluationParams};
+use crate::randomness::{self, SeededRandom, DOMAIN_INSPECTION_DRAW, DOMAIN_INSPECTORS, DOMAIN_ROUTERS, ROUND_INSPECTION, ROUND_SELECTION};
+use crate::selection;
+use crate::verification::{self, MessageCopy, Verification};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RouterStatus {
+    Active,
+    Jailed,
+    Exiting,
+}
+
+fn default_status() -> RouterStatus {
+    RouterStatus::Active
+}
+
+fn default_credibility() -> u32 {
+    50
+}
+
+fn default_times() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Router {
+    pub id: String,
+    #[serde(default = "default_credibility")]
+    pub credibility: u32,
+    #[serde(default = "default_status")]
+    pub status: RouterStatus,
+    /// the result of `Tokenomics::get_staking_weights`
+    #[serde(default)]
+    pub staking_weight: u128,
+    #[serde(default)]
+    pub offences: u32,
+    #[serde(default)]
+    pub last_offence: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WeightPolicy {
+    pub cred_ratio: u32,
+    pub stake_ratio: u32,
+    pub require_stake: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Params {
+    pub credibility: CredibilityRange,
+    /// `EvaluationParams::piecewise` if not set
+    pub evaluation: Option<EvaluationParams>,
+    pub vf_threshold: u128,
+    pub allow_single_copy: bool,
+    pub weight_policy: WeightPolicy,
+    pub inspection_rate: u128,
+    pub inspection_copy_count: u16,
+}
+
+impl Default for Params {
+    /// The same as the constructor of the contract
+    fn default() -> Self {
+        Params {
+            credibility: CredibilityRange::default(),
+            evaluation: None,
+            vf_threshold: 7000,
+            allow_single_copy: false,
+            weight_policy: WeightPolicy {
+                cred_ratio: 10000,
+                stake_ratio: 0,
+                require_stake: false,
+            },
+            inspection_rate: 0,
+            inspection_copy_count: 2,
+        }
+    }
+}
+
+/// Where the seed of a selection comes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Seed {
+    /// The seed recorded in `RoutersSelected`
+    Raw(String),
+    /// The signature supplied to `supply_vrf`
+    Vrf(String),
+    /// The beacon of commit-reveal when the message is announced or delivered
+    Beacon {
+        beacon: String,
+        from_chain: String,
+        msg_id: u128,
+    },
+}
+
+impl Seed {
+    fn resolve(&self, round: u8) -> Result<[u8; 32], String> {
+        match self {
+            Seed::Raw(seed) => to_seed(seed),
+            Seed::Vrf(signature) => Ok(randomness::vrf_seed(&crate::from_hex(signature)?)),
+            Seed::Beacon { beacon, from_chain, msg_id } => Ok(randomness::beacon_seed(&to_seed(beacon)?, from_chain, *msg_id, round)),
+        }
+    }
+}
+
+fn to_seed(hex: &str) -> Result<[u8; 32], String> {
+    let bytes = crate::from_hex(hex)?;
+    let mut seed = [0u8; 32];
+    if bytes.len() != seed.len() {
+        return Err(format!("a seed must be 32 bytes: {}", hex));
+    }
+
+    seed.copy_from_slice(&bytes);
+    Ok(seed)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Step {
+    /// `create_intervals`
+    Intervals,
+    /// `selection_statistic`
+    Statistic { seed: Seed, n: u16 },
+    /// The routers selected when a message is announced, or by `selection_test`
+    Select {
+        seed: Seed,
+        n: u16,
+        #[serde(default)]
+        excluded: Vec<String>,
+    },
+    /// The inspection drawn when the copies of a message are delivered by the `selected` routers
+    Inspect { seed: Seed, selected: Vec<String> },
+    /// `simu_message_verification` and the evaluation of the submitters, at the block number `block`
+    Verify {
+        copies: Vec<MessageCopy>,
+        #[serde(default)]
+        block: u32,
+    },
+    /// `do_honest`
+    Honest {
+        router: String,
+        #[serde(default = "default_times")]
+        times: u32,
+        #[serde(default)]
+        block: u32,
+    },
+    /// `do_evil`
+    Evil {
+        router: String,
+        #[serde(default = "default_times")]
+        times: u32,
+        #[serde(default)]
+        block: u32,
+    },
+    /// `set_router_status`
+    SetStatus { router: String, status: RouterStatus },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    #[serde(default)]
+    pub params: Params,
+    pub routers: Vec<Router>,
+    #[serde(default)]
+    pub steps: Vec<Step>,
+}
+
+/// The same as `SelectionInterval` of the contract
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interval {
+    pub id: String,
+    pub cre: u32,
+    pub weight: u128,
+    pub low: u128,
+    pub high: u128,
+    pub selected: u16,
+}
+
+/// The same as `CredibilityAdjustment` of the contract
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Adjustment {
+    pub router: String,
+    pub honest: bool,
+    pub cred_before: u32,
+    pub cred_after: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Intervals(Vec<Interval>),
+    Statistic(Vec<Interval>),
+    Select {
+        seed: String,
+        routers: Vec<String>,
+    },
+    Inspect {
+        seed: String,
+        held: bool,
+        inspectors: Vec<String>,
+    },
+    Verify {
+        verification: Verification,
+        adjustments: Vec<Adjustment>,
+    },
+    /// The same as `EvaluateResult::results`
+    Evaluate {
+        router: String,
+        results: Vec<u32>,
+    },
+    Status {
+        router: String,
+        status: RouterStatus,
+    },
+}
+
+/// The state of the replay
+pub struct Network {
+    params: Params,
+    evaluation: EvaluationParams,
+    routers: Vec<Router>,
+}
+
+impl Network {
+    pub fn new(params: Params, routers: Vec<Router>) -> Self {
+        let evaluation = params.evaluation.clone().unwrap_or_else(|| EvaluationParams::piecewise(&params.credibility));
+
+        Network {
+            params,
+            evaluation,
+            routers,
+        }
+    }
+
+    pub fn routers(&self) -> &[Router] {
+        &self.routers
+    }
+
+    fn position(&self, id: &str) -> Result<usize, String> {
+        self.routers.iter().position(|router| router.id == id).ok_or(format!("unknown router: {}", id))
+    }
+
+    /// The same as `selection_weight` of the contract
+    pub fn weight(&self, router: &Router) -> u128 {
+        let policy = &self.params.weight_policy;
+        if (router.status != RouterStatus::Active) || (policy.require_stake && (router.staking_weight == 0)) {
+            return 0;
+        }
+
+        (router.credibility as u128)
+            .saturating_mul(policy.cred_ratio as u128)
+            .saturating_add(router.staking_weight.saturating_mul(policy.stake_ratio as u128))
+            .min(selection::MAX_WEIGHT)
+    }
+
+    pub fn intervals(&self) -> Vec<Interval> {
+        let mut sum: u128 = 0;
+        let mut intervals = vec![];
+        for router in self.routers.iter().filter(|router| router.status == RouterStatus::Active) {
+            let weight = self.weight(router);
+            intervals.push(Interval {
+                id: router.id.clone(),
+                cre: router.credibility,
+                weight,
+                low: sum,
+                high: sum.saturating_add(weight),
+                selected: 0,
+            });
+            sum = sum.saturating_add(weight);
+        }
+
+        intervals
+    }
+
+    pub fn statistic(&self, seed: &[u8; 32], n: u16) -> Option<Vec<Interval>> {
+        let mut intervals = self.intervals();
+        let weights: Vec<u128> = intervals.iter().map(|ele| ele.weight).collect();
+        let mut rng = SeededRandom::new(seed, DOMAIN_ROUTERS);
+
+        for _ in 0..n {
+            let idx = selection::weighted_sample(&weights, &mut rng)?;
+            intervals[idx].selected += 1;
+        }
+
+        Some(intervals)
+    }
+
+    pub fn select(&self, seed: &[u8; 32], domain: u8, n: u16, excluded: &[String]) -> Result<Vec<String>, String> {
+        let weights: Vec<u128> = self.routers.iter().map(|router| self.weight(router)).collect();
+        let excluded = excluded.iter().map(|id| self.position(id)).collect::<Result<Vec<usize>, String>>()?;
+        let mut rng = SeededRandom::new(seed, domain);
+
+        Ok(selection::sample_without_replacement(&weights, n as usize, &excluded, &mut rng)
+            .into_iter()
+            .map(|idx| self.routers[idx].id.clone())
+            .collect())
+    }
+
+    /// The same as `draw_inspection` and `start_inspection` of the contract
+    pub fn inspect(&self, seed: &[u8; 32], selected: &[String]) -> Result<Vec<String>, String> {
+        if self.params.inspection_rate == 0 {
+            return Ok(vec![]);
+        }
+
+        let rand_num = selection::uniform_below(&mut SeededRandom::new(seed, DOMAIN_INSPECTION_DRAW), 10000);
+        if rand_num < self.params.inspection_rate {
+            self.select(seed, DOMAIN_INSPECTORS, self.params.inspection_copy_count, selected)
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    pub fn verify(&mut self, copies: &[MessageCopy], block: u32) -> Result<(Verification, Vec<Adjustment>), String> {
+        let verification = verification::verify(copies, self.params.vf_threshold, self.params.allow_single_copy, |id| {
+            self.routers.iter().find(|router| router.id == id).map(|router| router.credibility)
+        });
+
+        let mut adjustments = vec![];
+        if let Some(accepted) = verification.accepted {
+            for (idx, copy) in copies.iter().enumerate() {
+                let honest = idx == accepted;
+
+                for submitter in copy.submitters.iter() {
+                    if let Ok(pos) = self.position(submitter) {
+                        let cred_before = self.routers[pos].credibility;
+                        if honest {
+                            self.evaluate_honest(pos, block);
+                        } else {
+                            self.evaluate_evil(pos, block);
+                        }
+
+                        adjustments.push(Adjustment {
+                            router: submitter.clone(),
+                            honest,
+                            cred_before,
+                            cred_after: self.routers[pos].credibility,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok((verification, adjustments))
+    }
+
+    fn evaluate_honest(&mut self, pos: usize, block: u32) {
+        let router = &mut self.routers[pos];
+        let blocks_since_offence = router.last_offence.map(|offence| block.saturating_sub(offence));
+        router.credibility = self.evaluation.honest(&self.params.credibility, router.credibility, blocks_since_offence);
+    }
+
+    fn evaluate_evil(&mut self, pos: usize, block: u32) {
+        let router = &mut self.routers[pos];
+        router.credibility = self.evaluation.evil(&self.params.credibility, router.credibility, router.offences);
+        router.offences += 1;
+        router.last_offence = Some(block);
+    }
+
+    pub fn apply(&mut self, step: &Step) -> Result<Outcome, String> {
+        match step {
+            Step::Intervals => Ok(Outcome::Intervals(self.intervals())),
+            Step::Statistic { seed, n } => {
+                let seed = seed.resolve(ROUND_SELECTION)?;
+                Ok(Outcome::Statistic(self.statistic(&seed, *n).unwrap_or_default()))
+            }
+            Step::Select { seed, n, excluded } => {
+                let seed = seed.resolve(ROUND_SELECTION)?;
+                Ok(Outcome::Select {
+                    seed: crate::to_hex(&seed),
+                    routers: self.select(&seed, DOMAIN_ROUTERS, *n, excluded)?,
+                })
+            }
+            Step::Inspect { seed, selected } => {
+                let seed = seed.resolve(ROUND_INSPECTION)?;
+                let inspectors = self.inspect(&seed, selected)?;
+                Ok(Outcome::Inspect {
+                    seed: crate::to_hex(&seed),
+                    held: !inspectors.is_empty(),
+                    inspectors,
+                })
+            }
+            Step::Verify { copies, block } => {
+                let (verification, adjustments) = self.verify(copies, *block)?;
+                Ok(Outcome::Verify { verification, adjustments })
+            }
+            Step::Honest { router, times, block } | Step::Evil { router, times, block } => {
+                let pos = self.position(router)?;
+                let mut results = vec![self.routers[pos].credibility];
+                for _ in 0..*times {
+                    if let Step::Honest { .. } = step {
+                        self.evaluate_honest(pos, *block);
+                    } else {
+                        self.evaluate_evil(pos, *block);
+                    }
+                    results.push(self.routers[pos].credibility);
+                }
+
+                Ok(Outcome::Evaluate {
+                    router: router.clone(),
+                    results,
+                })
+            }
+            Step::SetStatus { router, status } => {
+                let pos = self.position(router)?;
+                self.routers[pos].status = *status;
+                Ok(Outcome::Status {
+                    router: router.clone(),
+                    status: *status,
+                })
+            }
+        }
+    }
+}
+
+/// Replay all the steps of `scenario`, stops at the first failed step
+pub fn replay(scenario: &Scenario) -> Result<Vec<Outcome>, String> {
+    let mut network = Network::new(scenario.params.clone(), scenario.routers.clone());
+
+    scenario.steps.iter().enumerate()
+        .map(|(idx, step)| network.apply(step).map_err(|e| format!("step {}: {}", idx, e)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scenario() -> Scenario {
+        serde_json::from_str(include_str!("../scenarios/basic.json")).unwrap()
+    }
+
+    #[test]
+    fn replay_is_deterministic() {
+        let scenario = scenario();
+        assert_eq!(replay(&scenario).unwrap(), replay(&scenario).unwrap());
+    }
+
+    #[test]
+    fn selected_routers_are_distinct_and_active() {
+        let scenario = scenario();
+        let network = Network::new(scenario.params.clone(), scenario.routers.clone());
+
+        for seed in 0..50u8 {
+            let routers = network.select(&[seed; 32], DOMAIN_ROUTERS, 3, &[]).unwrap();
+            assert_eq!(routers.len(), 3);
+            assert!(!routers.contains(&String::from("dave")));
+
+            let mut deduped = routers.clone();
+            deduped.sort();
+            deduped.dedup();
+            assert_eq!(deduped.len(), 3);
+        }
+    }
+
+    #[test]
+    fn verification_adjusts_the_credibility() {
+        let mut network = Network::new(Params::default(), scenario().routers);
+        let copies = vec![
+            MessageCopy { hash: String::from("0x01"), submitters: vec![String::from("alice"), String::from("bob")] },
+            MessageCopy { hash: String::from("0x02"), submitters: vec![String::from("charlie")] },
+        ];
+
+        let (verification, adjustments) = network.verify(&copies, 1).unwrap();
+        assert_eq!(verification.accepted, Some(0));
+        assert_eq!(adjustments.iter().map(|ele| ele.cred_after).collect::<Vec<u32>>(), vec![55, 64, 24]);
+        assert_eq!(network.routers()[2].offences, 1);
+    }
+}
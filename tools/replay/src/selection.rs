@@ -0,0 +1,73 @@
+//! Weighted router selection by `selection.rs` and `weight_tree.rs` of the contract
+//!
+//! The weights of the routers are laid out in a Fenwick tree on the host, one slot for every router in the order of the slots of the contract,
+//! and the routers are drawn by the same functions as the contract does.
+
+use algorithm_prototype::weight_tree::{self, FenwickNodes};
+
+pub use algorithm_prototype::selection::{uniform_below, weighted_sample, RandomSource};
+pub use algorithm_prototype::weight_tree::MAX_WEIGHT;
+
+/// The nodes of a Fenwick tree in memory, indexed from `1`
+struct Nodes(Vec<u128>);
+
+impl FenwickNodes for Nodes {
+    fn node(&self, idx: u64) -> u128 {
+        self.0.get(idx as usize).cloned().unwrap_or(0)
+    }
+
+    fn set_node(&mut self, idx: u64, value: u128) {
+        if self.0.len() <= idx as usize {
+            self.0.resize(idx as usize + 1, 0);
+        }
+        self.0[idx as usize] = value;
+    }
+}
+
+/// Draw at most `n` different indexes of `weights` without replacement, skipping `excluded`,
+/// as `WeightTree::sample` does with `weights` in the slot order
+pub fn sample_without_replacement<R: RandomSource>(weights: &[u128], n: usize, excluded: &[usize], rng: &mut R) -> Vec<usize> {
+    let mut nodes = Nodes(vec![]);
+    for (idx, weight) in weights.iter().enumerate() {
+        weight_tree::append(&mut nodes, idx as u64, *weight);
+    }
+
+    let removed = excluded.iter()
+        .filter_map(|idx| weights.get(*idx).map(|weight| (*idx as u64 + 1, *weight)))
+        .collect();
+
+    weight_tree::sample_without_replacement(&nodes, weights.len() as u64, n, removed, rng)
+        .into_iter()
+        .map(|slot| slot as usize - 1)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Sequence(Vec<u128>);
+
+    impl RandomSource for Sequence {
+        fn next_u128(&mut self) -> u128 {
+            self.0.remove(0)
+        }
+    }
+
+    #[test]
+    fn drawn_weights_are_removed() {
+        // 4 falls in slot 2 of [3, 0, 5, 1, 0, 2], then 9 % 6 = 3 falls in slot 3 of [3, 0, 0, 1, 0, 2]
+        let mut rng = Sequence(vec![4, 9]);
+        assert_eq!(sample_without_replacement(&[3, 0, 5, 1, 0, 2], 2, &[], &mut rng), vec![2, 3]);
+
+        // 0 is rejected as `2^128 % 5 == 1`
+        let mut rng = Sequence(vec![0, 1]);
+        assert_eq!(sample_without_replacement(&[3, 0, 5], 3, &[0], &mut rng), vec![2]);
+    }
+
+    #[test]
+    fn excluded_indexes_are_skipped_once() {
+        let mut rng = Sequence(vec![3]);
+        assert_eq!(sample_without_replacement(&[3, 0, 5], 3, &[0, 0, 7], &mut rng), vec![2]);
+    }
+}
@@ -0,0 +1,115 @@
+//! Message verification, the same as `simu_message_verification` of the contract
+
+use serde::{Deserialize, Serialize};
+
+/// The copies of a message with the same hash
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageCopy {
+    pub hash: String,
+    pub submitters: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Verification {
+    pub vf_passed: bool,
+    /// the credibility sum of every copy, as `VerifyInfo::cred_sum`
+    pub cred_sums: Vec<u128>,
+    /// the index of the accepted copy, whose submitters are evaluated as honest and the others as evil
+    pub accepted: Option<usize>,
+}
+
+/// Verify the copies of a message, `cred_of` returns the credibility of a registered router
+pub fn verify<F: Fn(&str) -> Option<u32>>(copies: &[MessageCopy], vf_threshold: u128, allow_single_copy: bool, cred_of: F) -> Verification {
+    let submitter_count: usize = copies.iter().map(|copy| copy.submitters.len()).sum();
+
+    if (submitter_count == 1) && !allow_single_copy {
+        Verification {
+            vf_passed: false,
+            cred_sums: vec![],
+            accepted: None,
+        }
+    } else if copies.len() > 1 {
+        let cred_sums: Vec<u128> = copies.iter()
+            .map(|copy| copy.submitters.iter().filter_map(|router| cred_of(router)).fold(0u128, |sum, cred| sum.saturating_add(cred as u128)))
+            .collect();
+        let total_cred = cred_sums.iter().fold(0u128, |sum, cred_sum| sum.saturating_add(*cred_sum));
+
+        let mut max_cred: (usize, u128) = (0, 0);
+        for (idx, cred_sum) in cred_sums.iter().enumerate() {
+            let ratio = cred_sum.saturating_mul(10000).checked_div(total_cred).unwrap_or(0);
+            if max_cred.1 < ratio {
+                max_cred = (idx, ratio);
+            }
+        }
+
+        // no copy is accepted if the total credibility is `0`
+        let vf_passed = (total_cred > 0) && (max_cred.1 >= vf_threshold);
+        Verification {
+            vf_passed,
+            cred_sums,
+            accepted: if vf_passed { Some(max_cred.0) } else { None },
+        }
+    } else if copies.len() == 1 {
+        Verification {
+            vf_passed: true,
+            cred_sums: vec![100],
+            accepted: Some(0),
+        }
+    } else {
+        Verification {
+            vf_passed: false,
+            cred_sums: vec![],
+            accepted: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn copy(hash: &str, submitters: &[&str]) -> MessageCopy {
+        MessageCopy {
+            hash: String::from(hash),
+            submitters: submitters.iter().map(|router| String::from(*router)).collect(),
+        }
+    }
+
+    fn cred_of(router: &str) -> Option<u32> {
+        match router {
+            "alice" | "bob" | "charlie" => Some(50),
+            "dave" => Some(10),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn the_copy_over_the_threshold_is_accepted() {
+        let copies = [copy("0x01", &["alice", "bob", "charlie"]), copy("0x02", &["dave"])];
+        let rst = verify(&copies, 7000, false, cred_of);
+
+        assert_eq!(rst.cred_sums, vec![150, 10]);
+        assert_eq!(rst.accepted, Some(0));
+
+        let rst = verify(&copies, 9500, false, cred_of);
+        assert!(!rst.vf_passed);
+    }
+
+    #[test]
+    fn a_single_copy_needs_the_allowance() {
+        let copies = [copy("0x01", &["alice"])];
+
+        assert!(!verify(&copies, 7000, false, cred_of).vf_passed);
+        assert_eq!(verify(&copies, 7000, true, cred_of).accepted, Some(0));
+    }
+
+    #[test]
+    fn copies_without_credibility_are_not_accepted() {
+        let copies = [copy("0x01", &["eve"]), copy("0x02", &["mallory"])];
+
+        let rst = verify(&copies, 0, false, cred_of);
+        assert_eq!(rst.cred_sums, vec![0, 0]);
+        assert!(!rst.vf_passed);
+        assert_eq!(rst.accepted, None);
+    }
+}